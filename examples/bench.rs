@@ -0,0 +1,93 @@
+//! Rough timings for the core map operations.
+//!
+//! Run with `cargo run --release --example bench [SIZE...]`. Without arguments the map
+//! is measured at 1K, 1M and 10M integer keys and at 1K, 100K and 1M long string keys,
+//! which are expensive to hash and compare. Every size is measured a few times and the
+//! fastest round is reported, which keeps noise from other processes out of the numbers.
+
+use std::{hash::Hash, hint::black_box, time::Instant};
+
+use trashmap::TrashMap;
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, f64) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed().as_secs_f64() * 1000.0)
+}
+
+const ROUNDS: usize = 3;
+
+/// Length of the string keys. They share a long prefix, so comparing two of them has
+/// to look at most of their bytes.
+const STRING_KEY_LEN: usize = 256;
+
+fn string_key(i: u64) -> String {
+    format!("{:x>width$}", i, width = STRING_KEY_LEN)
+}
+
+fn round<K: Hash + Eq + Clone>(keys: &[K]) -> [f64; 4] {
+    let (mut map, insert) = time(|| {
+        let mut map = TrashMap::new();
+        for (i, key) in keys.iter().enumerate() {
+            map.insert(key.clone(), i);
+        }
+        map
+    });
+    let (_, get) = time(|| {
+        for key in keys {
+            black_box(map.get(key));
+        }
+    });
+    let (_, iterate) = time(|| black_box(map.iter().map(|(_, v)| *v).sum::<usize>()));
+    let (_, remove) = time(|| {
+        for key in keys {
+            black_box(map.remove(key));
+        }
+    });
+    [insert, get, iterate, remove]
+}
+
+fn bench<K: Hash + Eq + Clone>(name: &str, keys: &[K]) {
+    let mut best = [f64::INFINITY; 4];
+    for _ in 0..ROUNDS {
+        for (best, timing) in best.iter_mut().zip(round(keys)) {
+            *best = best.min(timing);
+        }
+    }
+    let [insert, get, iterate, remove] = best;
+    println!(
+        "{:>6} {:>10} | insert {:>10.2} ms | get {:>10.2} ms | iterate {:>8.2} ms | remove {:>10.2} ms",
+        name,
+        keys.len(),
+        insert,
+        get,
+        iterate,
+        remove
+    );
+}
+
+fn bench_u64(size: u64) {
+    bench("u64", &(0..size).collect::<Vec<_>>());
+}
+
+fn bench_string(size: u64) {
+    bench("String", &(0..size).map(string_key).collect::<Vec<_>>());
+}
+
+fn main() {
+    let sizes: Vec<u64> = std::env::args()
+        .skip(1)
+        .map(|arg| arg.parse().expect("sizes must be integers"))
+        .collect();
+    if sizes.is_empty() {
+        [1_000, 1_000_000, 10_000_000]
+            .into_iter()
+            .for_each(bench_u64);
+        [1_000, 100_000, 1_000_000]
+            .into_iter()
+            .for_each(bench_string);
+    } else {
+        sizes.iter().copied().for_each(bench_u64);
+        sizes.iter().copied().for_each(bench_string);
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{BucketIndexing, GrowthPolicy, GrowthPolicyError, ResizeMode, TrashMap};
+
+/// Configures and creates a [`TrashMap`].
+///
+/// ```
+/// use trashmap::{TrashMap, TrashMapBuilder};
+///
+/// let map: TrashMap<u64, String> = TrashMapBuilder::new()
+///     .max_load_factor(0.9)
+///     .growth_factor(1.5)
+///     .capacity(1000)
+///     .build()
+///     .unwrap();
+/// assert!(map.capacity() >= 1000);
+/// ```
+#[derive(Clone, Debug)]
+pub struct TrashMapBuilder<S = RandomState> {
+    hash_builder: S,
+    capacity: usize,
+    indexing: BucketIndexing,
+    resize_mode: ResizeMode,
+    max_load_factor: f32,
+    min_load_factor: f32,
+    growth_factor: f32,
+    min_buckets: usize,
+}
+
+impl TrashMapBuilder<RandomState> {
+    pub fn new() -> Self {
+        let policy = GrowthPolicy::default();
+        TrashMapBuilder {
+            hash_builder: RandomState::new(),
+            capacity: 0,
+            indexing: BucketIndexing::default(),
+            resize_mode: ResizeMode::default(),
+            max_load_factor: policy.max_load_factor(),
+            min_load_factor: policy.min_load_factor(),
+            growth_factor: policy.growth_factor(),
+            min_buckets: policy.min_buckets(),
+        }
+    }
+}
+
+impl Default for TrashMapBuilder<RandomState> {
+    fn default() -> Self {
+        TrashMapBuilder::new()
+    }
+}
+
+impl<S> TrashMapBuilder<S> {
+    pub fn hasher<T: BuildHasher>(self, hash_builder: T) -> TrashMapBuilder<T> {
+        TrashMapBuilder {
+            hash_builder,
+            capacity: self.capacity,
+            indexing: self.indexing,
+            resize_mode: self.resize_mode,
+            max_load_factor: self.max_load_factor,
+            min_load_factor: self.min_load_factor,
+            growth_factor: self.growth_factor,
+            min_buckets: self.min_buckets,
+        }
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn indexing(mut self, indexing: BucketIndexing) -> Self {
+        self.indexing = indexing;
+        self
+    }
+
+    pub fn resize_mode(mut self, resize_mode: ResizeMode) -> Self {
+        self.resize_mode = resize_mode;
+        self
+    }
+
+    pub fn max_load_factor(mut self, max_load_factor: f32) -> Self {
+        self.max_load_factor = max_load_factor;
+        self
+    }
+
+    pub fn min_load_factor(mut self, min_load_factor: f32) -> Self {
+        self.min_load_factor = min_load_factor;
+        self
+    }
+
+    pub fn growth_factor(mut self, growth_factor: f32) -> Self {
+        self.growth_factor = growth_factor;
+        self
+    }
+
+    pub fn min_buckets(mut self, min_buckets: usize) -> Self {
+        self.min_buckets = min_buckets;
+        self
+    }
+
+    /// Sets all growth parameters at once from an already validated policy.
+    pub fn growth_policy(mut self, policy: GrowthPolicy) -> Self {
+        self.max_load_factor = policy.max_load_factor();
+        self.min_load_factor = policy.min_load_factor();
+        self.growth_factor = policy.growth_factor();
+        self.min_buckets = policy.min_buckets();
+        self
+    }
+
+    /// Creates the map, or returns an error if the growth parameters are inconsistent or
+    /// the bucket array for the requested capacity is too large.
+    pub fn build<K: Hash + Eq + PartialEq, V>(self) -> Result<TrashMap<K, V, S>, GrowthPolicyError>
+    where
+        S: BuildHasher,
+    {
+        let policy = GrowthPolicy::new(
+            self.max_load_factor,
+            self.min_load_factor,
+            self.growth_factor,
+            self.min_buckets,
+        )?;
+        let mut map =
+            TrashMap::try_from_parts(self.hash_builder, self.capacity, self.indexing, policy)
+                .ok_or(GrowthPolicyError::CapacityOverflow)?;
+        map.set_resize_mode(self.resize_mode);
+        Ok(map)
+    }
+}
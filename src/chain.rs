@@ -0,0 +1,103 @@
+use std::{
+    iter,
+    ops::{Deref, DerefMut},
+    option, vec,
+};
+
+/// Contiguous storage for the elements of a single bucket.
+///
+/// At the load factors the map runs at, most buckets hold at most one element, so the
+/// first element is stored inline in the bucket array and the chain only spills into a
+/// heap allocated `Vec` once a second element collides with it. Either way the
+/// elements are exposed as one slice.
+#[derive(Clone, Debug)]
+pub(crate) enum Chain<K, V> {
+    Inline(Option<Element<K, V>>),
+    Spilled(Vec<Element<K, V>>),
+}
+
+/// A key-value pair together with the full hash of its key.
+///
+/// Keeping the hash around means resizing never has to hash a key again, and a chain
+/// walk only calls `eq` on elements whose hash matches.
+#[derive(Clone, Debug)]
+pub(crate) struct Element<K, V> {
+    pub(crate) hash: u64,
+    pub(crate) key: K,
+    pub(crate) value: V,
+}
+
+impl<K, V> Element<K, V> {
+    pub(crate) fn into_pair(self) -> (K, V) {
+        (self.key, self.value)
+    }
+}
+
+pub(crate) type IntoIter<K, V> =
+    iter::Chain<option::IntoIter<Element<K, V>>, vec::IntoIter<Element<K, V>>>;
+
+impl<K, V> Chain<K, V> {
+    pub(crate) fn new() -> Self {
+        Chain::Inline(None)
+    }
+
+    /// Appends an element, returning a reference to it.
+    pub(crate) fn push(&mut self, element: Element<K, V>) -> &mut Element<K, V> {
+        match self {
+            Chain::Inline(slot @ None) => slot.insert(element),
+            Chain::Inline(slot) => {
+                let first = slot.take().expect("inline slot is occupied");
+                *self = Chain::Spilled(vec![first, element]);
+                self.last_mut().expect("chain is not empty")
+            }
+            Chain::Spilled(elements) => {
+                elements.push(element);
+                elements.last_mut().expect("chain is not empty")
+            }
+        }
+    }
+
+    /// Removes the element at `index` by moving the last element into its place.
+    pub(crate) fn swap_remove(&mut self, index: usize) -> Element<K, V> {
+        match self {
+            Chain::Inline(slot) => {
+                assert_eq!(index, 0, "chain index out of bounds");
+                slot.take().expect("chain index out of bounds")
+            }
+            Chain::Spilled(elements) => elements.swap_remove(index),
+        }
+    }
+}
+
+impl<K, V> Deref for Chain<K, V> {
+    type Target = [Element<K, V>];
+
+    fn deref(&self) -> &[Element<K, V>] {
+        match self {
+            Chain::Inline(slot) => slot.as_slice(),
+            Chain::Spilled(elements) => elements,
+        }
+    }
+}
+
+impl<K, V> DerefMut for Chain<K, V> {
+    fn deref_mut(&mut self) -> &mut [Element<K, V>] {
+        match self {
+            Chain::Inline(slot) => slot.as_mut_slice(),
+            Chain::Spilled(elements) => elements,
+        }
+    }
+}
+
+impl<K, V> IntoIterator for Chain<K, V> {
+    type Item = Element<K, V>;
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        let (inline, spilled) = match self {
+            Chain::Inline(slot) => (slot, Vec::new()),
+            Chain::Spilled(elements) => (None, elements),
+        };
+        inline.into_iter().chain(spilled)
+    }
+}
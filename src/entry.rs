@@ -0,0 +1,152 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{chain::Element, TrashMap};
+
+/// A view into a single entry of a [`TrashMap`], which is either occupied or vacant.
+///
+/// Created by [`TrashMap::entry`]. The bucket is resolved once when the entry is
+/// created, so inserting or modifying through it does not hash the key again.
+pub enum Entry<'a, K, V, S = RandomState> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+/// An entry whose key is already present in the map.
+pub struct OccupiedEntry<'a, K, V, S = RandomState> {
+    map: &'a mut TrashMap<K, V, S>,
+    bucket: usize,
+    index: usize,
+}
+
+/// An entry whose key is not present in the map.
+pub struct VacantEntry<'a, K, V, S = RandomState> {
+    map: &'a mut TrashMap<K, V, S>,
+    bucket: usize,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K: Hash + Eq + PartialEq, V, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K: Hash + Eq + PartialEq, V, S: BuildHasher> OccupiedEntry<'a, K, V, S> {
+    pub(crate) fn new(map: &'a mut TrashMap<K, V, S>, bucket: usize, index: usize) -> Self {
+        OccupiedEntry { map, bucket, index }
+    }
+
+    fn element(&self) -> &Element<K, V> {
+        &self.map.buckets[self.bucket].chain[self.index]
+    }
+
+    pub fn key(&self) -> &K {
+        &self.element().key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.element().value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.buckets[self.bucket].chain[self.index].value
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.buckets[self.bucket].chain[self.index].value
+    }
+
+    /// Replaces the value of the entry, returning the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let element = self.map.buckets[self.bucket].remove_at(self.index);
+        self.map.elements -= 1;
+        self.map.shrink_after_remove();
+        element
+    }
+}
+
+impl<'a, K: Hash + Eq + PartialEq, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    pub(crate) fn new(map: &'a mut TrashMap<K, V, S>, bucket: usize, hash: u64, key: K) -> Self {
+        VacantEntry {
+            map,
+            bucket,
+            hash,
+            key,
+        }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the key with the given value, returning a mutable reference to the value.
+    ///
+    /// If the new element would push the map over its load factor, the map grows first
+    /// and the bucket is resolved again from the hash computed by [`TrashMap::entry`].
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+        let mut bucket = self.bucket;
+        let load_factor = (map.elements + 1) as f32 / map.buckets.len() as f32;
+        if load_factor > map.policy.max_load_factor() {
+            map.grow();
+            bucket = map.indexer.index(self.hash);
+        }
+        map.elements += 1;
+        let element = Element {
+            hash: self.hash,
+            key: self.key,
+            value,
+        };
+        &mut map.buckets[bucket].chain.push(element).value
+    }
+}
@@ -0,0 +1,35 @@
+use std::borrow::Borrow;
+
+/// Key equivalence used for lookups in a [`TrashMap`](crate::TrashMap).
+///
+/// Every `Q` that `K` can be borrowed as is equivalent to `K`, so `&str` can be used to
+/// look up `String` keys. Types that are not reachable through [`Borrow`], such as a
+/// borrowed mirror of a composite key, can implement this trait directly. The
+/// implementation must agree with the [`Hash`](std::hash::Hash) of the key: equivalent
+/// values have to produce the same hash.
+///
+/// ```
+/// use trashmap::{Equivalent, TrashMap};
+///
+/// #[derive(Hash)]
+/// struct PairRef<'a>(&'a str, u32);
+///
+/// impl Equivalent<(String, u32)> for PairRef<'_> {
+///     fn equivalent(&self, key: &(String, u32)) -> bool {
+///         self.0 == key.0 && self.1 == key.1
+///     }
+/// }
+///
+/// let mut map = TrashMap::new();
+/// map.insert(("answer".to_string(), 42), true);
+/// assert_eq!(map.get(&PairRef("answer", 42)), Some(&true));
+/// ```
+pub trait Equivalent<K: ?Sized> {
+    fn equivalent(&self, key: &K) -> bool;
+}
+
+impl<Q: ?Sized + Eq, K: ?Sized + Borrow<Q>> Equivalent<K> for Q {
+    fn equivalent(&self, key: &K) -> bool {
+        self.eq(key.borrow())
+    }
+}
@@ -0,0 +1,260 @@
+//! A map whose entries expire after a time to live.
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::FusedIterator,
+    time::{Duration, Instant},
+};
+
+use crate::{Equivalent, Iter as MapIter, TrashMap};
+
+/// A source of the current time for an [`ExpiringTrashMap`].
+///
+/// Implement it for a clock that can be advanced by hand to test expiry without
+/// sleeping.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The real monotonic clock, [`Instant::now`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// A value with the instant it stops being visible, if any.
+#[derive(Clone, Debug)]
+struct Timed<V> {
+    value: V,
+    deadline: Option<Instant>,
+}
+
+impl<V> Timed<V> {
+    fn is_live(&self, now: Instant) -> bool {
+        self.deadline.is_none_or(|deadline| now < deadline)
+    }
+}
+
+/// A [`TrashMap`] whose entries can expire.
+///
+/// Entries inserted with [`insert_with_ttl`](ExpiringTrashMap::insert_with_ttl) are
+/// treated as absent once their time to live has passed, but keep their memory until
+/// they are reclaimed. [`purge_expired`](ExpiringTrashMap::purge_expired) sweeps the
+/// whole map. With a sample size set, every mutating operation additionally checks a
+/// few buckets in turn and reclaims the expired entries there, like Redis does, so
+/// the map stays lean without full sweeps.
+pub struct ExpiringTrashMap<K, V, C = SystemClock, S = RandomState> {
+    map: TrashMap<K, Timed<V>, S>,
+    clock: C,
+    sample_size: usize,
+    /// The bucket position the next sample starts at.
+    cursor: usize,
+}
+
+impl<K: Hash + Eq + PartialEq, V> ExpiringTrashMap<K, V, SystemClock, RandomState> {
+    pub fn new() -> Self {
+        ExpiringTrashMap::with_clock(SystemClock)
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, C: Clock> ExpiringTrashMap<K, V, C, RandomState> {
+    /// Creates an empty map which reads the time from `clock`.
+    pub fn with_clock(clock: C) -> Self {
+        ExpiringTrashMap::with_clock_and_hasher(clock, RandomState::new())
+    }
+}
+
+impl<K, V, C, S> ExpiringTrashMap<K, V, C, S> {
+    /// Returns the number of entries, including expired ones that were not reclaimed
+    /// yet.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+
+    /// Sets how many buckets every mutating operation checks for expired entries. Zero,
+    /// the default, turns sampling off.
+    pub fn set_sample_size(&mut self, sample_size: usize) {
+        self.sample_size = sample_size;
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, C: Clock, S: BuildHasher> ExpiringTrashMap<K, V, C, S> {
+    /// Creates an empty map which reads the time from `clock` and hashes its keys with
+    /// `hash_builder`.
+    pub fn with_clock_and_hasher(clock: C, hash_builder: S) -> Self {
+        ExpiringTrashMap {
+            map: TrashMap::with_hasher(hash_builder),
+            clock,
+            sample_size: 0,
+            cursor: 0,
+        }
+    }
+
+    /// Reclaims the expired entries of the next few buckets, if sampling is on.
+    fn sample(&mut self) {
+        if self.sample_size == 0 {
+            return;
+        }
+        let now = self.clock.now();
+        self.cursor = self
+            .map
+            .retain_buckets(self.cursor, self.sample_size, |_, timed| timed.is_live(now));
+    }
+
+    fn insert_timed(&mut self, key: K, value: V, deadline: Option<Instant>) -> Option<V> {
+        self.sample();
+        let now = self.clock.now();
+        let old = self.map.insert(key, Timed { value, deadline })?;
+        old.is_live(now).then_some(old.value)
+    }
+
+    /// Inserts a key-value pair that never expires, returning the previous value of the
+    /// key unless it had expired.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_timed(key, value, None)
+    }
+
+    /// Inserts a key-value pair that expires once `ttl` has passed, returning the
+    /// previous value of the key unless it had expired. A `ttl` too long to represent
+    /// as an [`Instant`] never expires.
+    pub fn insert_with_ttl(&mut self, key: K, value: V, ttl: Duration) -> Option<V> {
+        let deadline = self.clock.now().checked_add(ttl);
+        self.insert_timed(key, value, deadline)
+    }
+
+    /// Returns the value of a key, or `None` if it is absent or expired.
+    pub fn get<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<&V> {
+        let timed = self.map.get(key)?;
+        timed.is_live(self.clock.now()).then_some(&timed.value)
+    }
+
+    /// Returns the value of a key for modification, or `None` if it is absent or
+    /// expired. An expired entry is reclaimed right away.
+    pub fn get_mut<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<&mut V> {
+        self.sample();
+        let now = self.clock.now();
+        if !self.map.get(key)?.is_live(now) {
+            self.map.remove(key);
+            return None;
+        }
+        self.map.get_mut(key).map(|timed| &mut timed.value)
+    }
+
+    pub fn contains_key<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns how long a key has left to live, or `None` if it is absent, expired or
+    /// never expires.
+    pub fn ttl<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<Duration> {
+        let deadline = self.map.get(key)?.deadline?;
+        deadline
+            .checked_duration_since(self.clock.now())
+            .filter(|ttl| !ttl.is_zero())
+    }
+
+    /// Removes a key, returning its value unless it had expired.
+    pub fn remove<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<V> {
+        self.sample();
+        let now = self.clock.now();
+        let old = self.map.remove(key)?;
+        old.is_live(now).then_some(old.value)
+    }
+
+    /// Reclaims every expired entry, returning how many there were.
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let before = self.map.len();
+        self.map.retain(|_, timed| timed.is_live(now));
+        before - self.map.len()
+    }
+
+    /// Returns an iterator over the entries that have not expired.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.map.iter(),
+            now: self.clock.now(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.cursor = 0;
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V> Default for ExpiringTrashMap<K, V, SystemClock, RandomState> {
+    fn default() -> Self {
+        ExpiringTrashMap::new()
+    }
+}
+
+impl<K, V, C, S> fmt::Debug for ExpiringTrashMap<K, V, C, S>
+where
+    K: Hash + Eq + PartialEq + fmt::Debug,
+    V: fmt::Debug,
+    C: Clock,
+    S: BuildHasher,
+{
+    /// Formats the entries that have not expired as `{k: v, ...}`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of an [`ExpiringTrashMap`] that had not expired when
+/// it was created by [`ExpiringTrashMap::iter`].
+pub struct Iter<'a, K, V> {
+    inner: MapIter<'a, K, Timed<V>>,
+    now: Instant,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = self.now;
+        self.inner
+            .find(|(_, timed)| timed.is_live(now))
+            .map(|(key, timed)| (key, &timed.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+            now: self.now,
+        }
+    }
+}
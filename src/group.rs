@@ -0,0 +1,160 @@
+//! Control byte groups for [`SwissMap`](crate::SwissMap).
+//!
+//! A group is a window of [`GROUP_WIDTH`] consecutive control bytes which is matched
+//! against a byte all at once. With SSE2 this takes a single compare and movemask,
+//! everywhere else the portable implementation checks the bytes one by one.
+
+pub(crate) const GROUP_WIDTH: usize = 16;
+
+/// Marks a slot that has never been used. Probing stops at empty slots.
+pub(crate) const EMPTY: u8 = 0b1111_1111;
+/// Marks a slot whose element was removed. Probing continues past deleted slots.
+pub(crate) const DELETED: u8 = 0b1000_0000;
+
+/// Full slots store the top 7 bits of the hash of their key, so the high bit is clear.
+pub(crate) fn is_full(ctrl: u8) -> bool {
+    ctrl & 0x80 == 0
+}
+
+/// One bit per control byte in a group, set where the byte matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct BitMask(pub(crate) u16);
+
+impl BitMask {
+    pub(crate) fn any(self) -> bool {
+        self.0 != 0
+    }
+
+    pub(crate) fn lowest(self) -> Option<usize> {
+        if self.any() {
+            Some(self.0.trailing_zeros() as usize)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn leading_zeros(self) -> usize {
+        self.0.leading_zeros() as usize
+    }
+
+    pub(crate) fn trailing_zeros(self) -> usize {
+        self.0.trailing_zeros() as usize
+    }
+}
+
+impl Iterator for BitMask {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let bit = self.lowest()?;
+        self.0 &= self.0 - 1;
+        Some(bit)
+    }
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2"
+))]
+pub(crate) use sse2::Group;
+
+#[cfg(not(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2"
+)))]
+pub(crate) use generic::Group;
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse2"
+))]
+mod sse2 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{
+        __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8,
+    };
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{
+        __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_movemask_epi8, _mm_set1_epi8,
+    };
+
+    use super::{BitMask, EMPTY, GROUP_WIDTH};
+
+    #[derive(Clone, Copy)]
+    pub(crate) struct Group(__m128i);
+
+    impl Group {
+        /// Loads the first [`GROUP_WIDTH`] bytes of `ctrl`.
+        pub(crate) fn load(ctrl: &[u8]) -> Self {
+            assert!(
+                ctrl.len() >= GROUP_WIDTH,
+                "control bytes shorter than a group"
+            );
+            // SAFETY: the slice holds at least 16 bytes and the load is unaligned
+            Group(unsafe { _mm_loadu_si128(ctrl.as_ptr() as *const __m128i) })
+        }
+
+        pub(crate) fn match_byte(self, byte: u8) -> BitMask {
+            // SAFETY: SSE2 is enabled for the whole build, see the `cfg` on this module
+            unsafe {
+                let matches = _mm_cmpeq_epi8(self.0, _mm_set1_epi8(byte as i8));
+                BitMask(_mm_movemask_epi8(matches) as u16)
+            }
+        }
+
+        pub(crate) fn match_empty(self) -> BitMask {
+            self.match_byte(EMPTY)
+        }
+
+        /// Empty and deleted bytes are the ones with the high bit set.
+        pub(crate) fn match_empty_or_deleted(self) -> BitMask {
+            // SAFETY: SSE2 is enabled for the whole build, see the `cfg` on this module
+            unsafe { BitMask(_mm_movemask_epi8(self.0) as u16) }
+        }
+    }
+}
+
+#[cfg_attr(
+    all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        target_feature = "sse2"
+    ),
+    allow(dead_code)
+)]
+pub(crate) mod generic {
+    use super::{BitMask, EMPTY, GROUP_WIDTH};
+
+    #[derive(Clone, Copy)]
+    pub(crate) struct Group([u8; GROUP_WIDTH]);
+
+    impl Group {
+        /// Loads the first [`GROUP_WIDTH`] bytes of `ctrl`.
+        pub(crate) fn load(ctrl: &[u8]) -> Self {
+            let mut bytes = [0; GROUP_WIDTH];
+            bytes.copy_from_slice(&ctrl[..GROUP_WIDTH]);
+            Group(bytes)
+        }
+
+        fn mask_where(self, predicate: impl Fn(u8) -> bool) -> BitMask {
+            let mut mask = 0;
+            for (i, byte) in self.0.into_iter().enumerate() {
+                if predicate(byte) {
+                    mask |= 1 << i;
+                }
+            }
+            BitMask(mask)
+        }
+
+        pub(crate) fn match_byte(self, byte: u8) -> BitMask {
+            self.mask_where(|ctrl| ctrl == byte)
+        }
+
+        pub(crate) fn match_empty(self) -> BitMask {
+            self.match_byte(EMPTY)
+        }
+
+        pub(crate) fn match_empty_or_deleted(self) -> BitMask {
+            self.mask_where(|ctrl| ctrl & 0x80 != 0)
+        }
+    }
+}
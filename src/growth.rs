@@ -0,0 +1,121 @@
+use std::{error::Error, fmt};
+
+use crate::{TRASH_MAP_LOAD_FACTOR_THRESH, TRASH_MAP_MIN_LOAD_FACTOR, TRASH_MAP_START_SIZE};
+
+/// Growing by more than this at once wastes most of the new bucket array, and far
+/// larger factors overflow the bucket count after a handful of resizes.
+const MAX_GROWTH_FACTOR: f32 = 16.0;
+/// The largest bucket count a policy may demand before any element is inserted.
+const MAX_MIN_BUCKETS: usize = 1 << 30;
+
+/// When and how far a [`TrashMap`](crate::TrashMap) resizes its bucket array.
+///
+/// Higher load factors trade longer chains for less memory, lower ones the other way
+/// around. Use [`GrowthPolicy::new`] or the [`TrashMapBuilder`](crate::TrashMapBuilder)
+/// to construct a validated policy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GrowthPolicy {
+    max_load_factor: f32,
+    min_load_factor: f32,
+    growth_factor: f32,
+    min_buckets: usize,
+}
+
+/// The error returned when a [`GrowthPolicy`] is inconsistent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrowthPolicyError {
+    /// The maximum load factor is not a positive, finite number.
+    MaxLoadFactor,
+    /// The minimum load factor is negative, or so high that a freshly grown map would
+    /// immediately want to shrink again.
+    MinLoadFactor,
+    /// The growth factor is not greater than one and at most 16.
+    GrowthFactor,
+    /// The minimum bucket count is zero or above 2^30.
+    MinBuckets,
+    /// The bucket array needed for the requested capacity cannot be allocated.
+    CapacityOverflow,
+}
+
+impl fmt::Display for GrowthPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrowthPolicyError::MaxLoadFactor => {
+                f.write_str("max load factor must be positive and finite")
+            }
+            GrowthPolicyError::MinLoadFactor => f.write_str(
+                "min load factor must be at least zero and below the load factor after growing",
+            ),
+            GrowthPolicyError::GrowthFactor => {
+                f.write_str("growth factor must be greater than one and at most 16")
+            }
+            GrowthPolicyError::MinBuckets => {
+                f.write_str("min bucket count must be at least one and at most 2^30")
+            }
+            GrowthPolicyError::CapacityOverflow => f.write_str("capacity overflow"),
+        }
+    }
+}
+
+impl Error for GrowthPolicyError {}
+
+impl GrowthPolicy {
+    /// Creates a policy, checking that its parameters are consistent.
+    ///
+    /// The map grows by `growth_factor` once its load factor exceeds
+    /// `max_load_factor`, may shrink once it drops below `min_load_factor`, and never
+    /// uses fewer than `min_buckets` buckets. A `min_load_factor` of zero disables
+    /// shrinking.
+    pub fn new(
+        max_load_factor: f32,
+        min_load_factor: f32,
+        growth_factor: f32,
+        min_buckets: usize,
+    ) -> Result<Self, GrowthPolicyError> {
+        if !(max_load_factor.is_finite() && max_load_factor > 0.0) {
+            return Err(GrowthPolicyError::MaxLoadFactor);
+        }
+        if !(growth_factor > 1.0 && growth_factor <= MAX_GROWTH_FACTOR) {
+            return Err(GrowthPolicyError::GrowthFactor);
+        }
+        if !(min_load_factor >= 0.0 && min_load_factor < max_load_factor / growth_factor) {
+            return Err(GrowthPolicyError::MinLoadFactor);
+        }
+        if !(1..=MAX_MIN_BUCKETS).contains(&min_buckets) {
+            return Err(GrowthPolicyError::MinBuckets);
+        }
+        Ok(GrowthPolicy {
+            max_load_factor,
+            min_load_factor,
+            growth_factor,
+            min_buckets,
+        })
+    }
+
+    pub fn max_load_factor(&self) -> f32 {
+        self.max_load_factor
+    }
+
+    pub fn min_load_factor(&self) -> f32 {
+        self.min_load_factor
+    }
+
+    pub fn growth_factor(&self) -> f32 {
+        self.growth_factor
+    }
+
+    pub fn min_buckets(&self) -> usize {
+        self.min_buckets
+    }
+}
+
+impl Default for GrowthPolicy {
+    fn default() -> Self {
+        GrowthPolicy {
+            max_load_factor: TRASH_MAP_LOAD_FACTOR_THRESH,
+            min_load_factor: TRASH_MAP_MIN_LOAD_FACTOR,
+            growth_factor: 2.0,
+            min_buckets: TRASH_MAP_START_SIZE,
+        }
+    }
+}
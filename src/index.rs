@@ -0,0 +1,102 @@
+//! Reduction of hashes to bucket indices.
+
+use crate::prime;
+
+/// How a [`TrashMap`](crate::TrashMap) turns a hash into a bucket index.
+///
+/// The strategy also decides which bucket counts the map uses, so it is chosen once
+/// when the map is created.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BucketIndexing {
+    /// Prime bucket counts. The modulo is computed with a precomputed magic number
+    /// and two multiplications instead of a division.
+    #[default]
+    PrimeModulo,
+    /// Power of two bucket counts. The hash is mixed first, so that hashers with weak
+    /// low bits do not pile everything into a few buckets, and then masked.
+    PowerOfTwo,
+    /// Any bucket count. The hash is scaled into the bucket range with Lemire's
+    /// multiply-shift, which relies on the high bits of the hash.
+    FastRange,
+}
+
+impl BucketIndexing {
+    /// Returns the smallest bucket count usable with this strategy that is at least
+    /// `min_buckets`, or `None` on overflow.
+    pub(crate) fn bucket_count(self, min_buckets: usize) -> Option<usize> {
+        match self {
+            BucketIndexing::PrimeModulo => prime::next_prime(min_buckets),
+            BucketIndexing::PowerOfTwo => min_buckets.checked_next_power_of_two(),
+            BucketIndexing::FastRange => Some(min_buckets.max(1)),
+        }
+    }
+
+    /// Returns the bucket count to grow to from `buckets` when growing by
+    /// `growth_factor`, or `None` on overflow.
+    pub(crate) fn grown_bucket_count(self, buckets: usize, growth_factor: f32) -> Option<usize> {
+        let target = (buckets as f64 * growth_factor as f64).ceil();
+        if target >= usize::MAX as f64 {
+            return None;
+        }
+        let target = (target as usize).max(buckets + 1);
+        match self {
+            BucketIndexing::PrimeModulo => prime::next_bucket_prime(target),
+            BucketIndexing::PowerOfTwo | BucketIndexing::FastRange => self.bucket_count(target),
+        }
+    }
+}
+
+/// A [`BucketIndexing`] strategy prepared for one bucket count.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BucketIndexer {
+    indexing: BucketIndexing,
+    buckets: u64,
+    /// `ceil(2^128 / buckets)` for the prime modulo, unused otherwise.
+    magic: u128,
+}
+
+/// Finalizer of MurmurHash3, which lets every input bit affect the low output bits.
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+impl BucketIndexer {
+    pub(crate) fn new(indexing: BucketIndexing, buckets: usize) -> Self {
+        assert!(buckets > 0, "a map needs at least one bucket");
+        let magic = match indexing {
+            BucketIndexing::PrimeModulo if buckets > 1 => u128::MAX / buckets as u128 + 1,
+            _ => 0,
+        };
+        BucketIndexer {
+            indexing,
+            buckets: buckets as u64,
+            magic,
+        }
+    }
+
+    pub(crate) fn indexing(&self) -> BucketIndexing {
+        self.indexing
+    }
+
+    pub(crate) fn index(&self, hash: u64) -> usize {
+        let index = match self.indexing {
+            // Lemire, Kaser and Kurz, "Faster Remainder by Direct Computation": the low
+            // 128 bits of magic * hash hold the fractional part of hash / buckets, and
+            // multiplying that by buckets yields the remainder in the top bits
+            BucketIndexing::PrimeModulo if self.buckets > 1 => {
+                let fraction = self.magic.wrapping_mul(hash as u128);
+                let high = (fraction >> 64) * self.buckets as u128;
+                let low = (fraction as u64 as u128 * self.buckets as u128) >> 64;
+                ((high + low) >> 64) as u64
+            }
+            BucketIndexing::PrimeModulo => 0,
+            BucketIndexing::PowerOfTwo => mix(hash) & (self.buckets - 1),
+            BucketIndexing::FastRange => ((hash as u128 * self.buckets as u128) >> 64) as u64,
+        };
+        index as usize
+    }
+}
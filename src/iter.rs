@@ -0,0 +1,482 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+    iter::{self, FusedIterator},
+    slice, vec,
+};
+
+use crate::{
+    chain::{self, Element},
+    Bucket, TrashMap,
+};
+
+/// The new bucket array followed by the old one that is still being migrated.
+type Buckets<'a, K, V> = iter::Chain<slice::Iter<'a, Bucket<K, V>>, slice::Iter<'a, Bucket<K, V>>>;
+type BucketsMut<'a, K, V> =
+    iter::Chain<slice::IterMut<'a, Bucket<K, V>>, slice::IterMut<'a, Bucket<K, V>>>;
+type IntoBuckets<K, V> = iter::Chain<vec::IntoIter<Bucket<K, V>>, vec::IntoIter<Bucket<K, V>>>;
+
+impl<'a, K, V> IntoIterator for &'a Bucket<K, V> {
+    type Item = &'a Element<K, V>;
+    type IntoIter = slice::Iter<'a, Element<K, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chain.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut Bucket<K, V> {
+    type Item = &'a mut Element<K, V>;
+    type IntoIter = slice::IterMut<'a, Element<K, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chain.iter_mut()
+    }
+}
+
+impl<K, V> IntoIterator for Bucket<K, V> {
+    type Item = Element<K, V>;
+    type IntoIter = chain::IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chain.into_iter()
+    }
+}
+
+/// An iterator over the entries of a [`TrashMap`](crate::TrashMap), created by
+/// [`TrashMap::iter`](crate::TrashMap::iter).
+///
+/// During an incremental resize the buckets that were not migrated yet are visited
+/// after the new bucket array. The iterator counts down from the number of elements
+/// in the map, so its length is known without walking the buckets.
+pub struct Iter<'a, K, V> {
+    elements: iter::Flatten<Buckets<'a, K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    pub(crate) fn new(
+        buckets: &'a [Bucket<K, V>],
+        old_buckets: &'a [Bucket<K, V>],
+        len: usize,
+    ) -> Self {
+        Iter {
+            elements: buckets.iter().chain(old_buckets).flatten(),
+            remaining: len,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        self.remaining -= 1;
+        Some((&element.key, &element.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next_back()?;
+        self.remaining -= 1;
+        Some((&element.key, &element.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            elements: self.elements.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+/// A mutable iterator over the entries of a [`TrashMap`](crate::TrashMap), created by
+/// [`TrashMap::iter_mut`](crate::TrashMap::iter_mut).
+pub struct IterMut<'a, K, V> {
+    elements: iter::Flatten<BucketsMut<'a, K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> IterMut<'a, K, V> {
+    pub(crate) fn new(
+        buckets: &'a mut [Bucket<K, V>],
+        old_buckets: &'a mut [Bucket<K, V>],
+        len: usize,
+    ) -> Self {
+        IterMut {
+            elements: buckets.iter_mut().chain(old_buckets).flatten(),
+            remaining: len,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        self.remaining -= 1;
+        Some((&element.key, &mut element.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next_back()?;
+        self.remaining -= 1;
+        Some((&element.key, &mut element.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+/// An owning iterator over the entries of a [`TrashMap`](crate::TrashMap), created by
+/// its [`IntoIterator`] implementation.
+#[derive(Clone)]
+pub struct IntoIter<K, V> {
+    elements: iter::Flatten<IntoBuckets<K, V>>,
+    remaining: usize,
+}
+
+impl<K, V> IntoIter<K, V> {
+    /// Old buckets that were already migrated are empty, so all of them can be passed.
+    pub(crate) fn new(
+        buckets: Vec<Bucket<K, V>>,
+        old_buckets: Vec<Bucket<K, V>>,
+        len: usize,
+    ) -> Self {
+        IntoIter {
+            elements: buckets.into_iter().chain(old_buckets).flatten(),
+            remaining: len,
+        }
+    }
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        self.remaining -= 1;
+        Some(element.into_pair())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next_back()?;
+        self.remaining -= 1;
+        Some(element.into_pair())
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+/// An iterator over the keys of a [`TrashMap`](crate::TrashMap), created by
+/// [`TrashMap::keys`](crate::TrashMap::keys).
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Keys<'a, K, V> {
+    pub(crate) fn new(inner: Iter<'a, K, V>) -> Self {
+        Keys { inner }
+    }
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Keys<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+impl<K, V> Clone for Keys<'_, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An iterator over the values of a [`TrashMap`](crate::TrashMap), created by
+/// [`TrashMap::values`](crate::TrashMap::values).
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Values<'a, K, V> {
+    pub(crate) fn new(inner: Iter<'a, K, V>) -> Self {
+        Values { inner }
+    }
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Values<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+impl<K, V> Clone for Values<'_, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// A mutable iterator over the values of a [`TrashMap`](crate::TrashMap), created by
+/// [`TrashMap::values_mut`](crate::TrashMap::values_mut).
+pub struct ValuesMut<'a, K, V> {
+    inner: IterMut<'a, K, V>,
+}
+
+impl<'a, K, V> ValuesMut<'a, K, V> {
+    pub(crate) fn new(inner: IterMut<'a, K, V>) -> Self {
+        ValuesMut { inner }
+    }
+}
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<&'a mut V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for ValuesMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+/// An owning iterator over the keys of a [`TrashMap`](crate::TrashMap), created by
+/// [`TrashMap::into_keys`](crate::TrashMap::into_keys).
+#[derive(Clone)]
+pub struct IntoKeys<K, V> {
+    inner: IntoIter<K, V>,
+}
+
+impl<K, V> IntoKeys<K, V> {
+    pub(crate) fn new(inner: IntoIter<K, V>) -> Self {
+        IntoKeys { inner }
+    }
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoKeys<K, V> {
+    fn next_back(&mut self) -> Option<K> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {}
+
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+
+/// An owning iterator over the values of a [`TrashMap`](crate::TrashMap), created by
+/// [`TrashMap::into_values`](crate::TrashMap::into_values).
+#[derive(Clone)]
+pub struct IntoValues<K, V> {
+    inner: IntoIter<K, V>,
+}
+
+impl<K, V> IntoValues<K, V> {
+    pub(crate) fn new(inner: IntoIter<K, V>) -> Self {
+        IntoValues { inner }
+    }
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoValues<K, V> {
+    fn next_back(&mut self) -> Option<V> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {}
+
+impl<K, V> FusedIterator for IntoValues<K, V> {}
+
+/// A draining iterator over the entries of a [`TrashMap`](crate::TrashMap), created by
+/// [`TrashMap::drain`](crate::TrashMap::drain).
+///
+/// Empties one bucket at a time. Dropping the iterator drops the entries that were not
+/// yielded yet, so the map is always empty afterwards, but keeps its capacity.
+pub struct Drain<'a, K, V, S = RandomState> {
+    map: &'a mut TrashMap<K, V, S>,
+    position: usize,
+    chain: chain::IntoIter<K, V>,
+}
+
+impl<'a, K, V, S> Drain<'a, K, V, S> {
+    pub(crate) fn new(map: &'a mut TrashMap<K, V, S>) -> Self {
+        Drain {
+            map,
+            position: 0,
+            chain: chain::Chain::new().into_iter(),
+        }
+    }
+}
+
+impl<K, V, S> Iterator for Drain<'_, K, V, S> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(element) = self.chain.next() {
+                return Some(element.into_pair());
+            }
+            let bucket = self.map.bucket_at_mut(self.position)?;
+            let chain = std::mem::replace(&mut bucket.chain, chain::Chain::new());
+            // the whole chain leaves the map now, even if the iterator is leaked
+            self.map.elements -= chain.len();
+            self.chain = chain.into_iter();
+            self.position += 1;
+        }
+    }
+}
+
+impl<K, V, S> Drop for Drain<'_, K, V, S> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+        // every old bucket is empty now
+        self.map.migration = None;
+    }
+}
+
+/// An iterator that removes the entries of a [`TrashMap`](crate::TrashMap) matching a
+/// predicate, created by [`TrashMap::extract_if`](crate::TrashMap::extract_if).
+///
+/// Entries are only visited and removed as the iterator advances; dropping it early
+/// keeps the remaining entries. Once the iterator is exhausted the map may shrink.
+pub struct ExtractIf<'a, K, V, F, S = RandomState> {
+    map: &'a mut TrashMap<K, V, S>,
+    position: usize,
+    index: usize,
+    pred: F,
+}
+
+impl<'a, K, V, F, S> ExtractIf<'a, K, V, F, S> {
+    pub(crate) fn new(map: &'a mut TrashMap<K, V, S>, pred: F) -> Self {
+        ExtractIf {
+            map,
+            position: 0,
+            index: 0,
+            pred,
+        }
+    }
+}
+
+impl<K, V, F, S> Iterator for ExtractIf<'_, K, V, F, S>
+where
+    K: Hash + Eq + PartialEq,
+    F: FnMut(&K, &mut V) -> bool,
+    S: BuildHasher,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(bucket) = self.map.bucket_at_mut(self.position) {
+            while let Some(element) = bucket.chain.get_mut(self.index) {
+                if (self.pred)(&element.key, &mut element.value) {
+                    // the last element moves into this slot, so the index stays
+                    let removed = bucket.remove_at(self.index);
+                    self.map.elements -= 1;
+                    return Some(removed);
+                }
+                self.index += 1;
+            }
+            self.position += 1;
+            self.index = 0;
+        }
+        self.map.shrink_after_remove();
+        None
+    }
+}
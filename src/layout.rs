@@ -0,0 +1,105 @@
+use std::fmt;
+
+use crate::{Bucket, TrashMap};
+
+/// A view of the bucket array of a [`TrashMap`], created by
+/// [`TrashMap::debug_layout`].
+///
+/// Lists every occupied bucket with its chain, collapses runs of empty buckets into
+/// one line and shows the old bucket array while an incremental resize is running:
+///
+/// ```text
+/// 3 elements in 7 buckets (load factor 0.43, max 0.75)
+///   [0] 1: {7: "a"}
+///   [1..=3] empty
+///   [4] 2: {4: "b", 11: "c"}
+///   [5..=6] empty
+/// ```
+pub struct DebugLayout<'a, K, V, S> {
+    map: &'a TrashMap<K, V, S>,
+}
+
+impl<'a, K, V, S> DebugLayout<'a, K, V, S> {
+    pub(crate) fn new(map: &'a TrashMap<K, V, S>) -> Self {
+        DebugLayout { map }
+    }
+}
+
+fn write_buckets<K: fmt::Debug, V: fmt::Debug>(
+    f: &mut fmt::Formatter<'_>,
+    buckets: &[Bucket<K, V>],
+    first: usize,
+) -> fmt::Result {
+    let mut empty_since = None;
+    for (index, bucket) in buckets.iter().enumerate().map(|(i, b)| (first + i, b)) {
+        if bucket.chain.is_empty() {
+            empty_since.get_or_insert(index);
+            continue;
+        }
+        if let Some(start) = empty_since.take() {
+            write_empty(f, start, index - 1)?;
+        }
+        let chain = bucket.chain.iter().map(|e| (&e.key, &e.value));
+        writeln!(
+            f,
+            "  [{}] {}: {:?}",
+            index,
+            bucket.chain.len(),
+            DebugEntries(chain)
+        )?;
+    }
+    if let Some(start) = empty_since {
+        write_empty(f, start, first + buckets.len() - 1)?;
+    }
+    Ok(())
+}
+
+fn write_empty(f: &mut fmt::Formatter<'_>, start: usize, end: usize) -> fmt::Result {
+    if start == end {
+        writeln!(f, "  [{}] empty", start)
+    } else {
+        writeln!(f, "  [{}..={}] empty", start, end)
+    }
+}
+
+/// Formats key-value pairs like a map without collecting them first.
+struct DebugEntries<I>(I);
+
+impl<K: fmt::Debug, V: fmt::Debug, I: Iterator<Item = (K, V)> + Clone> fmt::Debug
+    for DebugEntries<I>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.clone()).finish()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Display for DebugLayout<'_, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let map = self.map;
+        writeln!(
+            f,
+            "{} elements in {} buckets (load factor {:.2}, max {:.2})",
+            map.elements,
+            map.buckets.len(),
+            map.elements as f32 / map.buckets.len() as f32,
+            map.policy.max_load_factor()
+        )?;
+        write_buckets(f, &map.buckets, 0)?;
+        if let Some(migration) = &map.migration {
+            writeln!(
+                f,
+                "resizing, {} of {} old buckets left",
+                migration.remaining().len(),
+                migration.buckets.len()
+            )?;
+            write_buckets(f, migration.remaining(), migration.next)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for DebugLayout<'_, K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -1,165 +1,920 @@
+mod builder;
+mod chain;
+mod entry;
+mod equivalent;
+pub mod expiring;
+mod group;
+mod growth;
+mod index;
+mod iter;
+mod layout;
+pub mod lru;
+pub mod multi;
+pub mod ordered;
+mod prime;
+mod resize;
+mod robin_hood;
+pub mod set;
+pub mod swiss;
+mod table;
+
+pub use builder::TrashMapBuilder;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+pub use equivalent::Equivalent;
+pub use expiring::{Clock, ExpiringTrashMap, SystemClock};
+pub use growth::{GrowthPolicy, GrowthPolicyError};
+pub use index::BucketIndexing;
+pub use iter::{
+    Drain, ExtractIf, IntoIter, IntoKeys, IntoValues, Iter, IterMut, Keys, Values, ValuesMut,
+};
+pub use layout::DebugLayout;
+pub use lru::LruTrashMap;
+pub use multi::TrashMultiMap;
+pub use ordered::OrderedTrashMap;
+pub use resize::ResizeMode;
+pub use robin_hood::{ProbeStats, RobinHoodMap};
+pub use set::TrashSet;
+pub use swiss::SwissMap;
+
+use chain::{Chain, Element};
+use index::BucketIndexer;
+use resize::Migration;
+
 use std::{
-    collections::{hash_map::DefaultHasher, LinkedList},
-    hash::{Hash, Hasher},
+    collections::hash_map::RandomState,
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hash},
+    ops::Index,
 };
 
 const TRASH_MAP_START_SIZE: usize = 3;
 const TRASH_MAP_LOAD_FACTOR_THRESH: f32 = 0.75;
+const TRASH_MAP_MIN_LOAD_FACTOR: f32 = 0.1;
 
 #[derive(Clone, Debug)]
 struct Bucket<K, V> {
-    chain: LinkedList<(K, V)>,
+    chain: Chain<K, V>,
 }
 
 impl<K: Eq + PartialEq, V> Bucket<K, V> {
-    fn insert(&mut self, key: K, value: V) {
-        if self.chain.is_empty() {
-            self.chain.push_back((key, value));
-        } else {
-            for element in self.chain.iter_mut() {
-                // entry is identical to existing entry
-                if element.0.eq(&key) {
-                    element.1 = value;
-                    return;
-                }
+    fn insert(&mut self, hash: u64, key: K, value: V) -> Option<V> {
+        for element in self.chain.iter_mut() {
+            // entry is identical to existing entry
+            if element.hash == hash && element.key.eq(&key) {
+                return Some(std::mem::replace(&mut element.value, value));
             }
-            self.chain.push_front((key, value));
         }
+        self.chain.push(Element { hash, key, value });
+        None
     }
 
-    fn get(&self, key: &K) -> Option<&V> {
-        if self.chain.len() == 0 {
-            None
-        } else {
-            for element in self.chain.iter() {
-                if element.0.eq(key) {
-                    return Some(&element.1);
-                }
+    fn find<Q: ?Sized + Equivalent<K>>(&self, hash: u64, key: &Q) -> Option<&Element<K, V>> {
+        let index = self.position(hash, key)?;
+        Some(&self.chain[index])
+    }
+
+    fn find_mut<Q: ?Sized + Equivalent<K>>(
+        &mut self,
+        hash: u64,
+        key: &Q,
+    ) -> Option<&mut Element<K, V>> {
+        let index = self.position(hash, key)?;
+        Some(&mut self.chain[index])
+    }
+
+    /// Finds the element for `key`, only comparing keys whose cached hash matches.
+    fn position<Q: ?Sized + Equivalent<K>>(&self, hash: u64, key: &Q) -> Option<usize> {
+        self.chain
+            .iter()
+            .position(|element| element.hash == hash && key.equivalent(&element.key))
+    }
+
+    fn remove_at(&mut self, index: usize) -> (K, V) {
+        // order within a chain does not matter, so the last element fills the gap
+        self.chain.swap_remove(index).into_pair()
+    }
+
+    fn remove<Q: ?Sized + Equivalent<K>>(&mut self, hash: u64, key: &Q) -> Option<(K, V)> {
+        let index = self.position(hash, key)?;
+        Some(self.remove_at(index))
+    }
+
+    /// Removes the elements `f` returns `false` for, returning how many were removed.
+    fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) -> usize {
+        let mut index = 0;
+        let mut removed = 0;
+        while let Some(element) = self.chain.get_mut(index) {
+            if f(&element.key, &mut element.value) {
+                index += 1;
+            } else {
+                self.remove_at(index);
+                removed += 1;
             }
-            None
         }
+        removed
     }
+}
 
-    fn remove(&mut self, key: &K) -> bool {
-        if self.chain.len() == 0 {
-            return false;
-        } else {
-            for (i, element) in self.chain.iter().enumerate() {
-                if element.0.eq(key) {
-                    let mut tail = self.chain.split_off(i);
-                    tail.pop_front();
-                    self.chain.append(&mut tail);
-                    return true;
-                }
-            }
-            false
+/// The error returned by [`TrashMap::get_many_mut`] when two of the requested keys
+/// refer to the same entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GetManyMutError;
+
+impl fmt::Display for GetManyMutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("requested keys are not disjoint")
+    }
+}
+
+impl Error for GetManyMutError {}
+
+/// The error returned by [`TrashMap::try_reserve`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested capacity does not fit into a bucket array.
+    CapacityOverflow,
+    /// The allocator could not provide memory for the bucket array.
+    AllocError,
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => f.write_str("capacity overflow"),
+            TryReserveError::AllocError => f.write_str("memory allocation failed"),
         }
     }
 }
 
-#[derive(Debug)]
-pub struct TrashMap<K, V> {
+impl Error for TryReserveError {}
+
+#[derive(Clone)]
+pub struct TrashMap<K, V, S = RandomState> {
     buckets: Vec<Bucket<K, V>>,
     elements: usize,
+    hash_builder: S,
+    indexer: BucketIndexer,
+    resize_mode: ResizeMode,
+    migration: Option<Migration<K, V>>,
+    policy: GrowthPolicy,
+    /// The capacity the caller asked for, which removals never shrink the map below.
+    min_capacity: usize,
 }
 
-fn is_prime(number: usize) -> bool {
-    if number & 1 == 0 {
-        return false;
+impl<K: Hash + Eq + PartialEq, V> TrashMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        TrashMap::with_hasher(RandomState::new())
     }
-    let closest_sqrt_integer = (number as f32).sqrt().ceil() as usize;
-    for i in 3..closest_sqrt_integer {
-        if number % i == 0 {
-            return false;
-        }
+
+    /// Creates an empty map which can hold at least `capacity` elements without growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        TrashMap::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+
+    /// Creates an empty map which reduces hashes to bucket indices with `indexing`.
+    pub fn with_indexing(indexing: BucketIndexing) -> Self {
+        TrashMap::with_hasher_and_indexing(RandomState::new(), indexing)
+    }
+}
+
+impl TrashMap<(), ()> {
+    /// Returns a builder to configure hashing, indexing, resizing and the growth policy
+    /// of a new map. The key and value types are picked when the map is built.
+    pub fn builder() -> TrashMapBuilder {
+        TrashMapBuilder::new()
     }
-    true
 }
 
-fn find_next_prime(prime: usize) -> usize {
-    let mut candidate = prime;
-    while !is_prime(candidate) {
-        candidate += 2;
+impl<K, V, S> TrashMap<K, V, S> {
+    /// Returns the bucket at `position`, counting through the new bucket array and then
+    /// the old buckets an incremental resize has not migrated yet.
+    fn bucket_at_mut(&mut self, position: usize) -> Option<&mut Bucket<K, V>> {
+        match position.checked_sub(self.buckets.len()) {
+            None => self.buckets.get_mut(position),
+            Some(old) => self.migration.as_mut()?.remaining_mut().get_mut(old),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements == 0
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let old_buckets = self.migration.as_ref().map_or(&[][..], |m| m.remaining());
+        Iter::new(&self.buckets, old_buckets, self.elements)
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        let old_buckets = self
+            .migration
+            .as_mut()
+            .map_or(&mut [][..], |m| m.remaining_mut());
+        IterMut::new(&mut self.buckets, old_buckets, self.elements)
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys::new(self.iter())
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values::new(self.iter())
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut::new(self.iter_mut())
+    }
+
+    /// Consumes the map, returning an iterator over its keys.
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys::new(self.into_iter())
+    }
+
+    /// Consumes the map, returning an iterator over its values.
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues::new(self.into_iter())
     }
-    return candidate;
 }
 
-impl<K: Hash + Eq + PartialEq, V> TrashMap<K, V> {
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> TrashMap<K, V, S> {
     fn make_buckets(count: usize) -> Vec<Bucket<K, V>> {
         let mut buckets = Vec::with_capacity(count);
         for _ in 0..count {
             buckets.push(Bucket {
-                chain: LinkedList::new(),
+                chain: Chain::new(),
             });
         }
         buckets
     }
 
-    pub fn new() -> Self {
-        TrashMap {
-            buckets: TrashMap::make_buckets(TRASH_MAP_START_SIZE),
+    fn try_make_buckets(count: usize) -> Result<Vec<Bucket<K, V>>, TryReserveError> {
+        let mut buckets = Vec::new();
+        buckets
+            .try_reserve_exact(count)
+            .map_err(|_| TryReserveError::AllocError)?;
+        buckets.extend((0..count).map(|_| Bucket {
+            chain: Chain::new(),
+        }));
+        Ok(buckets)
+    }
+
+    /// Picks the bucket count needed to hold `capacity` elements below the maximum load
+    /// factor, or `None` if such a bucket array could not be addressed.
+    fn bucket_count_for(
+        indexing: BucketIndexing,
+        policy: &GrowthPolicy,
+        capacity: usize,
+    ) -> Option<usize> {
+        let needed = (capacity as f64 / policy.max_load_factor() as f64)
+            .ceil()
+            .max(policy.min_buckets() as f64);
+        let max_buckets = isize::MAX as usize / std::mem::size_of::<Bucket<K, V>>();
+        if needed >= max_buckets as f64 {
+            return None;
+        }
+        indexing.bucket_count(needed as usize)
+    }
+
+    pub(crate) fn from_parts(
+        hash_builder: S,
+        capacity: usize,
+        indexing: BucketIndexing,
+        policy: GrowthPolicy,
+    ) -> Self {
+        TrashMap::try_from_parts(hash_builder, capacity, indexing, policy)
+            .expect("capacity overflow")
+    }
+
+    /// Like [`from_parts`](TrashMap::from_parts), but returns `None` if the bucket
+    /// array for `capacity` could not be addressed.
+    pub(crate) fn try_from_parts(
+        hash_builder: S,
+        capacity: usize,
+        indexing: BucketIndexing,
+        policy: GrowthPolicy,
+    ) -> Option<Self> {
+        let count = TrashMap::<K, V, S>::bucket_count_for(indexing, &policy, capacity)?;
+        Some(TrashMap {
+            buckets: TrashMap::<K, V, S>::make_buckets(count),
             elements: 0,
+            hash_builder,
+            indexer: BucketIndexer::new(indexing, count),
+            resize_mode: ResizeMode::default(),
+            migration: None,
+            policy,
+            min_capacity: capacity,
+        })
+    }
+
+    /// Creates an empty map which hashes its keys with `hash_builder`.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        TrashMap::with_hasher_and_indexing(hash_builder, BucketIndexing::default())
+    }
+
+    /// Creates an empty map which hashes its keys with `hash_builder` and reduces the
+    /// hashes to bucket indices with `indexing`.
+    pub fn with_hasher_and_indexing(hash_builder: S, indexing: BucketIndexing) -> Self {
+        TrashMap::from_parts(hash_builder, 0, indexing, GrowthPolicy::default())
+    }
+
+    /// Creates an empty map which can hold at least `capacity` elements without growing
+    /// and hashes its keys with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        TrashMap::from_parts(
+            hash_builder,
+            capacity,
+            BucketIndexing::default(),
+            GrowthPolicy::default(),
+        )
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    pub fn indexing(&self) -> BucketIndexing {
+        self.indexer.indexing()
+    }
+
+    pub fn growth_policy(&self) -> &GrowthPolicy {
+        &self.policy
+    }
+
+    /// Hashes a key. This is the only place the map calls `Hash`; elements keep their
+    /// hash, so moving them into another bucket array does not need the key.
+    fn hash<Q: ?Sized + Hash>(&self, key: &Q) -> u64 {
+        self.hash_builder.hash_one(key)
+    }
+
+    pub fn resize_mode(&self) -> ResizeMode {
+        self.resize_mode
+    }
+
+    /// Changes how the map grows and shrinks. Switching to [`ResizeMode::Immediate`] completes a
+    /// resize that is still in progress.
+    pub fn set_resize_mode(&mut self, resize_mode: ResizeMode) {
+        self.resize_mode = resize_mode;
+        if resize_mode == ResizeMode::Immediate {
+            self.finish_migration();
+        }
+    }
+
+    /// Returns whether an incremental resize is in progress, i.e. whether elements are
+    /// still spread over two bucket arrays.
+    pub fn is_resizing(&self) -> bool {
+        self.migration.is_some()
+    }
+
+    /// Moves all elements of the old bucket at `index` into the new bucket array.
+    fn drain_old_bucket(
+        indexer: &BucketIndexer,
+        buckets: &mut [Bucket<K, V>],
+        migration: &mut Migration<K, V>,
+        index: usize,
+    ) {
+        let chain = std::mem::replace(&mut migration.buckets[index].chain, Chain::new());
+        for element in chain {
+            buckets[indexer.index(element.hash)].chain.push(element);
         }
     }
 
-    fn hash(len: usize, key: &K) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        key.hash(&mut hasher);
-        hasher.finish() % (len as u64)
+    /// Moves up to `count` old buckets into the new bucket array, ending the resize
+    /// once the old array is drained.
+    fn migrate(&mut self, count: usize) {
+        let Some(migration) = &mut self.migration else {
+            return;
+        };
+        let end = migration
+            .next
+            .saturating_add(count)
+            .min(migration.buckets.len());
+        for index in migration.next..end {
+            TrashMap::<K, V, S>::drain_old_bucket(
+                &self.indexer,
+                &mut self.buckets,
+                migration,
+                index,
+            );
+        }
+        migration.next = end;
+        if end == migration.buckets.len() {
+            self.migration = None;
+        }
+    }
+
+    /// Advances an incremental resize by one step and moves the old bucket the key with
+    /// `hash` lives in, so the caller only has to look at the new bucket array.
+    fn migrate_for(&mut self, hash: u64) {
+        if let Some(step) = self.migration.as_ref().map(|migration| migration.step) {
+            self.migrate(step);
+        }
+        if let Some(migration) = &mut self.migration {
+            let index = migration.indexer.index(hash);
+            if index >= migration.next {
+                TrashMap::<K, V, S>::drain_old_bucket(
+                    &self.indexer,
+                    &mut self.buckets,
+                    migration,
+                    index,
+                );
+            }
+        }
+    }
+
+    fn finish_migration(&mut self) {
+        self.migrate(usize::MAX);
+    }
+
+    /// Returns the old bucket the key with `hash` would still be in during an
+    /// incremental resize.
+    fn old_bucket(&self, hash: u64) -> Option<&Bucket<K, V>> {
+        let migration = self.migration.as_ref()?;
+        migration.buckets.get(migration.indexer.index(hash))
     }
 
     fn compute_load_factor(&self) -> f32 {
         self.elements as f32 / self.buckets.len() as f32
     }
 
+    /// Returns the number of elements the map can hold without growing.
+    pub fn capacity(&self) -> usize {
+        (self.buckets.len() as f64 * self.policy.max_load_factor() as f64) as usize
+    }
+
+    /// Reserves room for at least `additional` more elements, rehashing at most once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows the bucket array.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self
+            .elements
+            .checked_add(additional)
+            .expect("capacity overflow");
+        if required > self.capacity() {
+            let count =
+                TrashMap::<K, V, S>::bucket_count_for(self.indexing(), &self.policy, required)
+                    .expect("capacity overflow");
+            self.rehash(TrashMap::<K, V, S>::make_buckets(count));
+        }
+    }
+
+    /// Like [`reserve`](TrashMap::reserve), but returns an error instead of panicking or
+    /// aborting when the capacity overflows or the allocation fails.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .elements
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if required > self.capacity() {
+            let count =
+                TrashMap::<K, V, S>::bucket_count_for(self.indexing(), &self.policy, required)
+                    .ok_or(TryReserveError::CapacityOverflow)?;
+            self.rehash(TrashMap::<K, V, S>::try_make_buckets(count)?);
+        }
+        Ok(())
+    }
+
+    /// Shrinks the bucket array as much as possible while keeping the load factor below
+    /// its threshold.
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the bucket array so it can still hold at least `min_capacity` elements
+    /// without growing. Does nothing if the map is already smaller.
+    ///
+    /// Removals never shrink the map below `min_capacity` afterwards, or below the
+    /// capacity it has now if that is smaller. The same holds for the capacity passed
+    /// to [`with_capacity`](TrashMap::with_capacity), until it is lowered again here.
+    /// [`reserve`](TrashMap::reserve) only makes room once and does not keep the map
+    /// from shrinking after removals.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let required = self.elements.max(min_capacity);
+        if required < self.capacity() {
+            let count =
+                TrashMap::<K, V, S>::bucket_count_for(self.indexing(), &self.policy, required)
+                    .expect("capacity overflow");
+            if count < self.buckets.len() {
+                self.rehash(TrashMap::<K, V, S>::make_buckets(count));
+            }
+        }
+        self.min_capacity = min_capacity.min(self.capacity());
+    }
+
     fn grow(&mut self) {
-        let new_size = find_next_prime(self.buckets.len() * 2 + 1);
-        let new_buckets: Vec<Bucket<K, V>> = TrashMap::make_buckets(new_size);
-        let old_buckets = std::mem::replace(&mut self.buckets, new_buckets);
-        for (key, value) in old_buckets.into_iter().flat_map(|b| b.chain.into_iter()) {
-            TrashMap::insert_into_buckets(&mut self.buckets, key, value);
+        let new_size = self
+            .indexing()
+            .grown_bucket_count(self.buckets.len(), self.policy.growth_factor())
+            .expect("capacity overflow");
+        self.resize_to(new_size);
+    }
+
+    /// Shrinks the bucket array once the load factor drops below the policy's minimum.
+    ///
+    /// The new size puts the load factor halfway between the minimum and the maximum,
+    /// so that neither a few more insertions nor a few more removals near the boundary
+    /// resize the map again right away. It never goes below the capacity the caller
+    /// asked for.
+    fn shrink_after_remove(&mut self) {
+        let min_load_factor = self.policy.min_load_factor();
+        if min_load_factor == 0.0
+            || self.compute_load_factor() >= min_load_factor
+            || self.buckets.len() <= self.policy.min_buckets()
+        {
+            return;
+        }
+        let target_load_factor = (min_load_factor + self.policy.max_load_factor()) / 2.0;
+        let needed = (self.elements as f64 / target_load_factor as f64).ceil() as usize;
+        let reserved =
+            (self.min_capacity as f64 / self.policy.max_load_factor() as f64).ceil() as usize;
+        let new_size = self
+            .indexing()
+            .bucket_count(needed.max(reserved).max(self.policy.min_buckets()));
+        if let Some(new_size) = new_size.filter(|&new_size| new_size < self.buckets.len()) {
+            self.resize_to(new_size);
         }
     }
 
-    fn insert_into_buckets(buckets: &mut Vec<Bucket<K, V>>, key: K, value: V) {
-        let hash = TrashMap::<K, V>::hash(buckets.len(), &key);
-        let bucket = &mut buckets[hash as usize];
-        bucket.insert(key, value);
+    /// Moves the elements into `new_size` buckets, right away or incrementally depending
+    /// on the resize mode.
+    fn resize_to(&mut self, new_size: usize) {
+        let new_buckets = TrashMap::<K, V, S>::make_buckets(new_size);
+        match self.resize_mode {
+            ResizeMode::Immediate => self.rehash(new_buckets),
+            ResizeMode::Incremental { buckets_per_op } => {
+                // only a bulk operation like `retain` can get here mid-migration
+                self.finish_migration();
+                let step = self.migration_step(buckets_per_op, new_size);
+                let indexer = BucketIndexer::new(self.indexing(), new_size);
+                self.migration = Some(Migration {
+                    buckets: std::mem::replace(&mut self.buckets, new_buckets),
+                    indexer: std::mem::replace(&mut self.indexer, indexer),
+                    next: 0,
+                    step,
+                });
+            }
+        }
     }
 
-    pub fn insert(&mut self, key: K, value: V) {
-        TrashMap::insert_into_buckets(&mut self.buckets, key, value);
-        self.elements += 1;
-        if self.compute_load_factor() > TRASH_MAP_LOAD_FACTOR_THRESH {
-            self.grow();
+    /// Picks how many old buckets every operation migrates after resizing to
+    /// `new_size` buckets: at least `buckets_per_op`, and enough to drain the current
+    /// bucket array before as many insertions or removals as it takes to resize again.
+    fn migration_step(&self, buckets_per_op: usize, new_size: usize) -> usize {
+        let grow_at = (new_size as f64 * self.policy.max_load_factor() as f64) as usize;
+        let mut headroom = grow_at.saturating_sub(self.elements);
+        if self.policy.min_load_factor() > 0.0 {
+            let shrink_at = (new_size as f64 * self.policy.min_load_factor() as f64) as usize;
+            headroom = headroom.min(self.elements.saturating_sub(shrink_at));
         }
+        let headroom = headroom.max(1);
+        buckets_per_op.max(self.buckets.len().div_ceil(headroom))
     }
 
-    pub fn remove(&mut self, key: &K) -> bool {
-        let hash = TrashMap::<K, V>::hash(self.buckets.len(), &key);
-        let bucket = &mut self.buckets[hash as usize];
-        let removed = bucket.remove(key);
-        if removed {
+    fn rehash(&mut self, new_buckets: Vec<Bucket<K, V>>) {
+        self.finish_migration();
+        self.indexer = BucketIndexer::new(self.indexing(), new_buckets.len());
+        let old_buckets = std::mem::replace(&mut self.buckets, new_buckets);
+        // keys are unique already, so elements are moved without hashing or comparing them
+        for element in old_buckets.into_iter().flat_map(|b| b.chain.into_iter()) {
+            self.buckets[self.indexer.index(element.hash)]
+                .chain
+                .push(element);
+        }
+    }
+
+    /// Inserts a key-value pair into the map.
+    ///
+    /// If the map already contained the key, the value is replaced and the old value
+    /// is returned. Only genuinely new keys count towards the load factor.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hash(&key);
+        self.migrate_for(hash);
+        let previous = self.buckets[self.indexer.index(hash)].insert(hash, key, value);
+        if previous.is_none() {
+            self.elements += 1;
+            if self.compute_load_factor() > self.policy.max_load_factor() {
+                self.grow();
+            }
+        }
+        previous
+    }
+
+    /// Removes a key from the map, returning its value if the key was present.
+    pub fn remove<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<V> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes a key from the map, returning the stored key and value if the key was present.
+    pub fn remove_entry<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<(K, V)> {
+        let hash = self.hash(key);
+        self.migrate_for(hash);
+        let removed = self.buckets[self.indexer.index(hash)].remove(hash, key);
+        if removed.is_some() {
             self.elements -= 1;
+            self.shrink_after_remove();
         }
         removed
     }
 
-    pub fn len(&self) -> usize {
-        self.elements
+    /// Removes all elements, keeping the bucket array. An incremental resize in
+    /// progress is abandoned, since there is nothing left to migrate.
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            bucket.chain = Chain::new();
+        }
+        self.migration = None;
+        self.elements = 0;
+    }
+
+    /// Removes all elements, returning them as an iterator. The map keeps its bucket
+    /// array and is empty once the iterator is dropped, even if it was not used up.
+    pub fn drain(&mut self) -> Drain<'_, K, V, S> {
+        Drain::new(self)
+    }
+
+    /// Keeps only the elements `f` returns `true` for, visiting each bucket once.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        let old_buckets = self
+            .migration
+            .as_mut()
+            .map_or(&mut [][..], |m| m.remaining_mut());
+        for bucket in self.buckets.iter_mut().chain(old_buckets) {
+            self.elements -= bucket.retain(&mut f);
+        }
+        self.shrink_after_remove();
+    }
+
+    /// Like [`retain`](TrashMap::retain), but only visits `count` buckets starting at
+    /// `position`, counting like [`bucket_at_mut`](TrashMap::bucket_at_mut) and
+    /// wrapping around at the end. Returns the position to continue at.
+    pub(crate) fn retain_buckets<F: FnMut(&K, &mut V) -> bool>(
+        &mut self,
+        mut position: usize,
+        count: usize,
+        mut f: F,
+    ) -> usize {
+        for _ in 0..count {
+            let bucket = match self.bucket_at_mut(position) {
+                Some(bucket) => bucket,
+                None => {
+                    position = 0;
+                    &mut self.buckets[0]
+                }
+            };
+            let removed = bucket.retain(&mut f);
+            self.elements -= removed;
+            position += 1;
+        }
+        self.shrink_after_remove();
+        position
+    }
+
+    /// Returns an iterator that removes and yields the elements `pred` returns `true`
+    /// for. Elements are only tested as the iterator advances, so dropping it early
+    /// keeps the rest of the map untouched.
+    pub fn extract_if<F: FnMut(&K, &mut V) -> bool>(
+        &mut self,
+        pred: F,
+    ) -> ExtractIf<'_, K, V, F, S> {
+        ExtractIf::new(self, pred)
     }
 
-    pub fn get(&self, key: &K) -> Option<&V> {
-        let hash = TrashMap::<K, V>::hash(self.buckets.len(), &key);
-        let bucket = &self.buckets[hash as usize];
-        bucket.get(key)
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = self.hash(&key);
+        self.migrate_for(hash);
+        let bucket = self.indexer.index(hash);
+        match self.buckets[bucket].position(hash, &key) {
+            Some(index) => Entry::Occupied(OccupiedEntry::new(self, bucket, index)),
+            None => Entry::Vacant(VacantEntry::new(self, bucket, hash, key)),
+        }
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
-        self.buckets
+    /// Returns a reference to the value of a key.
+    ///
+    /// The key may be any type equivalent to the stored key type, e.g. `&str` for
+    /// `String` keys, so lookups do not need to allocate an owned key.
+    pub fn get<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<&V> {
+        self.find(key).map(|element| &element.value)
+    }
+
+    /// Returns the stored key and the value of a key.
+    pub fn get_key_value<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<(&K, &V)> {
+        self.find(key).map(|element| (&element.key, &element.value))
+    }
+
+    fn find<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<&Element<K, V>> {
+        let hash = self.hash(key);
+        let bucket = &self.buckets[self.indexer.index(hash)];
+        bucket
+            .find(hash, key)
+            .or_else(|| self.old_bucket(hash)?.find(hash, key))
+    }
+
+    /// Looks up the element of a key for modification. The caller may replace the
+    /// stored key with an equal one, which hashes the same.
+    pub(crate) fn find_mut<Q: ?Sized + Hash + Equivalent<K>>(
+        &mut self,
+        key: &Q,
+    ) -> Option<&mut Element<K, V>> {
+        let hash = self.hash(key);
+        self.migrate_for(hash);
+        self.buckets[self.indexer.index(hash)].find_mut(hash, key)
+    }
+
+    pub fn contains_key<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get_mut<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<&mut V> {
+        self.find_mut(key).map(|element| &mut element.value)
+    }
+
+    /// Returns mutable references to the values of several keys at once.
+    ///
+    /// Keys that are not present yield `None`. If two keys refer to the same entry,
+    /// [`GetManyMutError`] is returned instead, since the references would alias.
+    pub fn get_many_mut<Q: ?Sized + Hash + Equivalent<K>, const N: usize>(
+        &mut self,
+        keys: [&Q; N],
+    ) -> Result<[Option<&mut V>; N], GetManyMutError> {
+        let hashes = keys.map(|key| self.hash(key));
+        for hash in hashes {
+            self.migrate_for(hash);
+        }
+        let locations = std::array::from_fn::<_, N, _>(|i| {
+            let bucket = self.indexer.index(hashes[i]);
+            self.buckets[bucket]
+                .position(hashes[i], keys[i])
+                .map(|index| (bucket, index))
+        });
+        for (i, location) in locations.iter().enumerate() {
+            if location.is_some() && locations[..i].contains(location) {
+                return Err(GetManyMutError);
+            }
+        }
+
+        // visit the buckets in ascending order so each one can be split off the table
+        // and walked once, handing out disjoint references without unsafe code
+        let mut order: [usize; N] = std::array::from_fn(|i| i);
+        order.sort_unstable_by_key(|&i| locations[i]);
+        let mut pending = order
             .iter()
-            .flat_map(|b| b.chain.iter())
-            .map(|e| (&e.0, &e.1))
+            .copied()
+            .filter(|&i| locations[i].is_some())
+            .peekable();
+        let mut values: [Option<&mut V>; N] = std::array::from_fn(|_| None);
+        let mut rest = self.buckets.as_mut_slice();
+        let mut offset = 0;
+        while let Some(&first) = pending.peek() {
+            let (hash, _) = locations[first].expect("pending locations are present");
+            let (bucket, tail) = std::mem::take(&mut rest)[hash - offset..]
+                .split_first_mut()
+                .expect("bucket index out of bounds");
+            rest = tail;
+            offset = hash + 1;
+            let mut elements = bucket.chain.iter_mut().enumerate();
+            while let Some(&i) = pending.peek() {
+                match locations[i] {
+                    Some((bucket_hash, index)) if bucket_hash == hash => {
+                        let (_, element) = elements
+                            .find(|(position, _)| *position == index)
+                            .expect("chain index out of bounds");
+                        values[i] = Some(&mut element.value);
+                        pending.next();
+                    }
+                    _ => break,
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    /// Returns a view that formats the bucket array instead of just the entries: which
+    /// buckets are occupied, what their chains hold and, during an incremental resize,
+    /// the old buckets that were not migrated yet.
+    pub fn debug_layout(&self) -> DebugLayout<'_, K, V, S> {
+        DebugLayout::new(self)
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for TrashMap<K, V, S> {
+    /// Formats the entries as `{k: v, ...}` in iteration order.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher + Default> Default for TrashMap<K, V, S> {
+    fn default() -> Self {
+        TrashMap::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V: PartialEq, S: BuildHasher> PartialEq for TrashMap<K, V, S> {
+    /// Two maps are equal if they hold the same entries, regardless of the order they
+    /// were inserted in or how their buckets are laid out.
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V: Eq, S: BuildHasher> Eq for TrashMap<K, V, S> {}
+
+impl<K, Q, V, S> Index<&Q> for TrashMap<K, V, S>
+where
+    K: Hash + Eq + PartialEq,
+    Q: ?Sized + Hash + Equivalent<K>,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    /// Returns a reference to the value of a key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not present in the map.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in TrashMap")
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> Extend<(K, V)> for TrashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        // keys already in the map do not need room, so only reserve for all of them
+        // when the map is empty
+        let (lower, _) = iter.size_hint();
+        self.reserve(if self.is_empty() {
+            lower
+        } else {
+            lower.div_ceil(2)
+        });
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<'a, K, V, S> Extend<(&'a K, &'a V)> for TrashMap<K, V, S>
+where
+    K: Hash + Eq + PartialEq + Copy,
+    V: Copy,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (&'a K, &'a V)>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|(&key, &value)| (key, value)));
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher + Default> FromIterator<(K, V)>
+    for TrashMap<K, V, S>
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = TrashMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, const N: usize> From<[(K, V); N]> for TrashMap<K, V> {
+    fn from(entries: [(K, V); N]) -> Self {
+        TrashMap::from_iter(entries)
+    }
+}
+
+impl<'a, K: Hash + Eq + PartialEq, V, S: BuildHasher> IntoIterator for &'a TrashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K: Hash + Eq + PartialEq, V, S: BuildHasher> IntoIterator for &'a mut TrashMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, S> IntoIterator for TrashMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        let old_buckets = self.migration.map_or_else(Vec::new, |m| m.buckets);
+        IntoIter::new(self.buckets, old_buckets, self.elements)
     }
 }
 
@@ -0,0 +1,341 @@
+//! A fixed capacity cache that evicts the least recently used entry.
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::FusedIterator,
+};
+
+use crate::{chain::Element, table::IndexTable, Equivalent};
+
+/// Marks the end of the recency list.
+const NIL: usize = usize::MAX;
+
+#[derive(Clone, Debug)]
+struct Node<K, V> {
+    element: Element<K, V>,
+    /// The next more recently used node.
+    prev: usize,
+    /// The next less recently used node.
+    next: usize,
+}
+
+/// What [`LruTrashMap::put`] pushed out of the cache.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Put<K, V> {
+    /// The key was already cached, this is its old value.
+    Replaced(V),
+    /// The cache was full and this least recently used entry was evicted. A cache with
+    /// zero capacity evicts the new pair right away.
+    Evicted(K, V),
+}
+
+/// A map holding at most `capacity` entries, which evicts the least recently used
+/// entry to make room for a new one.
+///
+/// The entries live in a dense `Vec` and are threaded into a doubly linked recency
+/// list by their positions, while a hash table maps each key's hash to its position.
+/// Looking up, promoting, inserting and evicting are all O(1).
+pub struct LruTrashMap<K, V, S = RandomState> {
+    nodes: Vec<Node<K, V>>,
+    table: IndexTable,
+    /// The most recently used node.
+    head: usize,
+    /// The least recently used node.
+    tail: usize,
+    capacity: usize,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq + PartialEq, V> LruTrashMap<K, V, RandomState> {
+    /// Creates an empty cache which holds at most `capacity` entries.
+    pub fn new(capacity: usize) -> Self {
+        LruTrashMap::with_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> LruTrashMap<K, V, S> {
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the number of entries the cache holds before it starts evicting.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns an iterator over the entries from the most to the least recently used,
+    /// without promoting any of them.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            nodes: &self.nodes,
+            front: self.head,
+            back: self.tail,
+            remaining: self.nodes.len(),
+        }
+    }
+
+    /// Returns the least recently used entry without promoting it.
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        let node = self.nodes.get(self.tail)?;
+        Some((&node.element.key, &node.element.value))
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.table.clear();
+        self.head = NIL;
+        self.tail = NIL;
+    }
+
+    /// Takes the node at `index` out of the recency list.
+    fn unlink(&mut self, index: usize) {
+        let Node { prev, next, .. } = self.nodes[index];
+        match prev {
+            NIL => self.head = next,
+            prev => self.nodes[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.nodes[next].prev = prev,
+        }
+    }
+
+    /// Puts the node at `index` at the front of the recency list.
+    fn push_front(&mut self, index: usize) {
+        self.nodes[index].prev = NIL;
+        self.nodes[index].next = self.head;
+        match self.head {
+            NIL => self.tail = index,
+            head => self.nodes[head].prev = index,
+        }
+        self.head = index;
+    }
+
+    /// Marks the node at `index` as the most recently used.
+    fn promote(&mut self, index: usize) {
+        if self.head != index {
+            self.unlink(index);
+            self.push_front(index);
+        }
+    }
+
+    /// Removes the node at `index`, moving the last node into its position.
+    fn remove_node(&mut self, index: usize) -> (K, V) {
+        self.unlink(index);
+        self.table.remove(self.nodes[index].element.hash, index);
+        let last = self.nodes.len() - 1;
+        if index != last {
+            let Node {
+                element,
+                prev,
+                next,
+            } = &self.nodes[last];
+            self.table.relocate(element.hash, last, index);
+            let (prev, next) = (*prev, *next);
+            match prev {
+                NIL => self.head = index,
+                prev => self.nodes[prev].next = index,
+            }
+            match next {
+                NIL => self.tail = index,
+                next => self.nodes[next].prev = index,
+            }
+        }
+        self.nodes.swap_remove(index).element.into_pair()
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> LruTrashMap<K, V, S> {
+    /// Creates an empty cache which holds at most `capacity` entries and hashes its
+    /// keys with `hash_builder`.
+    ///
+    /// The capacity is only a limit, memory is allocated as entries come in.
+    pub fn with_hasher(capacity: usize, hash_builder: S) -> Self {
+        LruTrashMap {
+            nodes: Vec::new(),
+            table: IndexTable::with_capacity(0),
+            head: NIL,
+            tail: NIL,
+            capacity,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    fn find<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<usize> {
+        let hash = self.hash_builder.hash_one(key);
+        self.table
+            .find(hash, |index| key.equivalent(&self.nodes[index].element.key))
+    }
+
+    /// Returns the value of a key and marks it as the most recently used.
+    pub fn get<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<&V> {
+        let index = self.find(key)?;
+        self.promote(index);
+        Some(&self.nodes[index].element.value)
+    }
+
+    /// Returns the value of a key for modification and marks it as the most recently
+    /// used.
+    pub fn get_mut<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<&mut V> {
+        let index = self.find(key)?;
+        self.promote(index);
+        Some(&mut self.nodes[index].element.value)
+    }
+
+    /// Returns the value of a key without changing how recently it was used.
+    pub fn peek<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<&V> {
+        let index = self.find(key)?;
+        Some(&self.nodes[index].element.value)
+    }
+
+    /// Returns whether the key is cached, without changing how recently it was used.
+    pub fn contains_key<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Inserts a key-value pair as the most recently used entry.
+    ///
+    /// If the key was already cached, its value is replaced and the old value is
+    /// returned as [`Put::Replaced`]. Otherwise, if the cache is full, the least
+    /// recently used entry is evicted and returned as [`Put::Evicted`].
+    pub fn put(&mut self, key: K, value: V) -> Option<Put<K, V>> {
+        let hash = self.hash_builder.hash_one(&key);
+        let nodes = &self.nodes;
+        if let Some(index) = self
+            .table
+            .find(hash, |index| nodes[index].element.key == key)
+        {
+            self.promote(index);
+            let old = std::mem::replace(&mut self.nodes[index].element.value, value);
+            return Some(Put::Replaced(old));
+        }
+        if self.capacity == 0 {
+            return Some(Put::Evicted(key, value));
+        }
+        let evicted = if self.nodes.len() >= self.capacity {
+            self.pop_lru().map(|(key, value)| Put::Evicted(key, value))
+        } else {
+            None
+        };
+        let index = self.nodes.len();
+        self.table.insert(hash, index);
+        self.nodes.push(Node {
+            element: Element { hash, key, value },
+            prev: NIL,
+            next: NIL,
+        });
+        self.push_front(index);
+        evicted
+    }
+
+    /// Removes a key, returning its value if it was cached.
+    pub fn pop<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<V> {
+        let index = self.find(key)?;
+        Some(self.remove_node(index).1)
+    }
+
+    /// Removes and returns the least recently used entry.
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        match self.tail {
+            NIL => None,
+            tail => Some(self.remove_node(tail)),
+        }
+    }
+
+    /// Changes the capacity, evicting the least recently used entries that no longer
+    /// fit.
+    pub fn resize(&mut self, capacity: usize) {
+        while self.nodes.len() > capacity {
+            self.pop_lru();
+        }
+        self.capacity = capacity;
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for LruTrashMap<K, V, S> {
+    fn clone(&self) -> Self {
+        LruTrashMap {
+            nodes: self.nodes.clone(),
+            table: self.table.clone(),
+            head: self.head,
+            tail: self.tail,
+            capacity: self.capacity,
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for LruTrashMap<K, V, S> {
+    /// Formats the entries as `{k: v, ...}` from the most to the least recently used.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a LruTrashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of an [`LruTrashMap`] from the most to the least
+/// recently used, created by [`LruTrashMap::iter`].
+pub struct Iter<'a, K, V> {
+    nodes: &'a [Node<K, V>],
+    front: usize,
+    back: usize,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = &self.nodes[self.front];
+        self.front = node.next;
+        self.remaining -= 1;
+        Some((&node.element.key, &node.element.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = &self.nodes[self.back];
+        self.back = node.prev;
+        self.remaining -= 1;
+        Some((&node.element.key, &node.element.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter { ..*self }
+    }
+}
@@ -0,0 +1,317 @@
+//! A map holding any number of values per key, and its iterators.
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::FusedIterator,
+    slice,
+};
+
+use crate::{Equivalent, Iter as MapIter, Keys as MapKeys, TrashMap};
+
+/// A hash map that keeps every value inserted for a key instead of replacing it.
+///
+/// The values of a key are stored in a `Vec` in insertion order, behind a single
+/// [`TrashMap`] entry, so the key is only hashed and stored once. Keys without values
+/// are removed, so [`key_count`](TrashMultiMap::key_count) only counts keys that have
+/// at least one value, while [`len`](TrashMultiMap::len) counts the values.
+pub struct TrashMultiMap<K, V, S = RandomState> {
+    map: TrashMap<K, Vec<V>, S>,
+    len: usize,
+}
+
+impl<K: Hash + Eq + PartialEq, V> TrashMultiMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        TrashMultiMap::with_hasher(RandomState::new())
+    }
+
+    /// Creates an empty map which can hold at least `capacity` keys without growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        TrashMultiMap {
+            map: TrashMap::with_capacity(capacity),
+            len: 0,
+        }
+    }
+}
+
+impl<K, V, S> TrashMultiMap<K, V, S> {
+    /// Returns the number of values over all keys.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct keys.
+    pub fn key_count(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns an iterator over every key-value pair, yielding a key once per value.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            groups: self.map.iter(),
+            current: None,
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the keys and all values of each key.
+    pub fn iter_grouped(&self) -> Grouped<'_, K, V> {
+        Grouped {
+            inner: self.map.iter(),
+        }
+    }
+
+    /// Returns an iterator over the distinct keys.
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys {
+            inner: self.map.keys(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> TrashMultiMap<K, V, S> {
+    /// Creates an empty map which hashes its keys with `hash_builder`.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        TrashMultiMap {
+            map: TrashMap::with_hasher(hash_builder),
+            len: 0,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        self.map.hasher()
+    }
+
+    /// Appends a value to the values of a key.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.map.entry(key).or_default().push(value);
+        self.len += 1;
+    }
+
+    /// Returns all values of a key in insertion order, or an empty slice if the key is
+    /// absent.
+    pub fn get_all<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> &[V] {
+        self.map.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns all values of a key for modification.
+    pub fn get_all_mut<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> &mut [V] {
+        self.map.get_mut(key).map_or(&mut [], Vec::as_mut_slice)
+    }
+
+    /// Returns the first value inserted for a key.
+    pub fn get<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<&V> {
+        self.get_all(key).first()
+    }
+
+    pub fn contains_key<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Removes and returns the value inserted last for a key, i.e. the last value of
+    /// [`get_all`](TrashMultiMap::get_all), while [`get`](TrashMultiMap::get) returns the
+    /// first one. The key goes away with its last value.
+    pub fn remove_one<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<V> {
+        let values = self.map.get_mut(key)?;
+        let value = values.pop();
+        if values.is_empty() {
+            self.map.remove(key);
+        }
+        self.len -= 1;
+        value
+    }
+
+    /// Removes a key, returning all of its values in insertion order.
+    pub fn remove_all<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Vec<V> {
+        let values = self.map.remove(key).unwrap_or_default();
+        self.len -= values.len();
+        values
+    }
+
+    /// Keeps only the key-value pairs `f` returns `true` for, dropping keys that lose
+    /// all their values.
+    pub fn retain<F: FnMut(&K, &V) -> bool>(&mut self, mut f: F) {
+        let mut len = 0;
+        self.map.retain(|key, values| {
+            values.retain(|value| f(key, value));
+            len += values.len();
+            !values.is_empty()
+        });
+        self.len = len;
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.len = 0;
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for TrashMultiMap<K, V, S> {
+    fn clone(&self) -> Self {
+        TrashMultiMap {
+            map: self.map.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for TrashMultiMap<K, V, S> {
+    /// Formats the map as `{k: [v, ...], ...}`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter_grouped()).finish()
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher + Default> Default for TrashMultiMap<K, V, S> {
+    fn default() -> Self {
+        TrashMultiMap::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V: PartialEq, S: BuildHasher> PartialEq for TrashMultiMap<K, V, S> {
+    /// Two maps are equal if they hold the same keys with the same values in the same
+    /// order per key.
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.map == other.map
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V: Eq, S: BuildHasher> Eq for TrashMultiMap<K, V, S> {}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> Extend<(K, V)> for TrashMultiMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher + Default> FromIterator<(K, V)>
+    for TrashMultiMap<K, V, S>
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = TrashMultiMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a TrashMultiMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// An iterator over every key-value pair of a [`TrashMultiMap`], created by
+/// [`TrashMultiMap::iter`]. The values of a key are visited one after another, in
+/// insertion order.
+pub struct Iter<'a, K, V> {
+    groups: MapIter<'a, K, Vec<V>>,
+    current: Option<(&'a K, slice::Iter<'a, V>)>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, values)) = &mut self.current {
+                if let Some(value) = values.next() {
+                    self.remaining -= 1;
+                    return Some((*key, value));
+                }
+            }
+            let (key, values) = self.groups.next()?;
+            self.current = Some((key, values.iter()));
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            groups: self.groups.clone(),
+            current: self.current.clone(),
+            remaining: self.remaining,
+        }
+    }
+}
+
+/// An iterator over the keys of a [`TrashMultiMap`] together with all of their
+/// values, created by [`TrashMultiMap::iter_grouped`].
+pub struct Grouped<'a, K, V> {
+    inner: MapIter<'a, K, Vec<V>>,
+}
+
+impl<'a, K, V> Iterator for Grouped<'a, K, V> {
+    type Item = (&'a K, &'a [V]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|(key, values)| (key, values.as_slice()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Grouped<'_, K, V> {}
+
+impl<K, V> FusedIterator for Grouped<'_, K, V> {}
+
+impl<K, V> Clone for Grouped<'_, K, V> {
+    fn clone(&self) -> Self {
+        Grouped {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An iterator over the distinct keys of a [`TrashMultiMap`], created by
+/// [`TrashMultiMap::keys`].
+pub struct Keys<'a, K, V> {
+    inner: MapKeys<'a, K, Vec<V>>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+impl<K, V> Clone for Keys<'_, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
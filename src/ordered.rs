@@ -0,0 +1,543 @@
+//! A map that remembers the order its keys were inserted in, and its iterators.
+
+use std::{
+    cmp::Ordering,
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::FusedIterator,
+    ops::Index,
+    slice, vec,
+};
+
+use crate::{chain::Element, table::IndexTable, Equivalent};
+
+/// A hash map that iterates in insertion order.
+///
+/// The entries live in a dense `Vec` in the order they were inserted, and a hash table
+/// maps each key's hash to its position in that `Vec`. Lookups by key stay O(1), and
+/// entries can also be addressed by position, like a slice.
+///
+/// Inserting a key that is already present updates its value in place and keeps its
+/// position. [`swap_remove`](OrderedTrashMap::swap_remove) is O(1) but moves the last
+/// entry into the gap, [`shift_remove`](OrderedTrashMap::shift_remove) keeps the order
+/// of the remaining entries at O(n).
+pub struct OrderedTrashMap<K, V, S = RandomState> {
+    entries: Vec<Element<K, V>>,
+    table: IndexTable,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq + PartialEq, V> OrderedTrashMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        OrderedTrashMap::with_hasher(RandomState::new())
+    }
+
+    /// Creates an empty map which can hold at least `capacity` entries without growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        OrderedTrashMap::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> OrderedTrashMap<K, V, S> {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entry at position `index`.
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        let element = self.entries.get(index)?;
+        Some((&element.key, &element.value))
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        let element = self.entries.get_mut(index)?;
+        Some((&element.key, &mut element.value))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.get_index(0)
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.get_index(self.len().checked_sub(1)?)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.entries.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys { inner: self.iter() }
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values { inner: self.iter() }
+    }
+
+    /// Removes all entries, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.table.clear();
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> OrderedTrashMap<K, V, S> {
+    /// Creates an empty map which hashes its keys with `hash_builder`.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        OrderedTrashMap::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// Creates an empty map which can hold at least `capacity` entries without growing
+    /// and hashes its keys with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        OrderedTrashMap {
+            entries: Vec::with_capacity(capacity),
+            table: IndexTable::with_capacity(capacity),
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    fn find<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<(u64, usize)> {
+        let hash = self.hash_builder.hash_one(key);
+        let index = self
+            .table
+            .find(hash, |index| key.equivalent(&self.entries[index].key))?;
+        Some((hash, index))
+    }
+
+    /// Inserts a key-value pair at the end of the map.
+    ///
+    /// If the map already contained the key, the value is replaced in place, the key
+    /// keeps its position and the old value is returned.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_full(key, value).1
+    }
+
+    /// Like [`insert`](OrderedTrashMap::insert), but also returns the position of the
+    /// key.
+    pub fn insert_full(&mut self, key: K, value: V) -> (usize, Option<V>) {
+        let hash = self.hash_builder.hash_one(&key);
+        let entries = &self.entries;
+        if let Some(index) = self.table.find(hash, |index| entries[index].key == key) {
+            let old = std::mem::replace(&mut self.entries[index].value, value);
+            return (index, Some(old));
+        }
+        let index = self.entries.len();
+        self.table.insert(hash, index);
+        self.entries.push(Element { hash, key, value });
+        (index, None)
+    }
+
+    pub fn get<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<&V> {
+        let (_, index) = self.find(key)?;
+        Some(&self.entries[index].value)
+    }
+
+    pub fn get_key_value<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<(&K, &V)> {
+        let (_, index) = self.find(key)?;
+        self.get_index(index)
+    }
+
+    pub fn get_mut<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<&mut V> {
+        let (_, index) = self.find(key)?;
+        Some(&mut self.entries[index].value)
+    }
+
+    pub fn contains_key<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Returns the position of a key.
+    pub fn get_index_of<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<usize> {
+        self.find(key).map(|(_, index)| index)
+    }
+
+    /// Removes a key in O(1) by moving the last entry into its position.
+    pub fn swap_remove<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<V> {
+        let (_, index) = self.find(key)?;
+        self.swap_remove_index(index).map(|(_, value)| value)
+    }
+
+    /// Removes the entry at `index` by moving the last entry into its position.
+    pub fn swap_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        let hash = self.entries.get(index)?.hash;
+        self.table.remove(hash, index);
+        let last = self.entries.len() - 1;
+        if index != last {
+            self.table.relocate(self.entries[last].hash, last, index);
+        }
+        Some(self.entries.swap_remove(index).into_pair())
+    }
+
+    /// Removes a key and shifts all entries after it one position down, which keeps
+    /// their order but takes O(n).
+    pub fn shift_remove<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<V> {
+        let (_, index) = self.find(key)?;
+        self.shift_remove_index(index).map(|(_, value)| value)
+    }
+
+    /// Removes the entry at `index` and shifts all entries after it one position down.
+    pub fn shift_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        let hash = self.entries.get(index)?.hash;
+        self.table.remove(hash, index);
+        // in ascending order, so no two slots ever hold the same position
+        for position in index + 1..self.entries.len() {
+            self.table
+                .relocate(self.entries[position].hash, position, position - 1);
+        }
+        Some(self.entries.remove(index).into_pair())
+    }
+
+    /// Removes and returns the last entry.
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        self.swap_remove_index(self.len().checked_sub(1)?)
+    }
+
+    /// Moves the entry at `from` to position `to`, shifting the entries in between by
+    /// one position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from` or `to` are out of bounds.
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        let len = self.entries.len();
+        assert!(from < len && to < len, "index out of bounds");
+        if from == to {
+            return;
+        }
+        // park the moving entry outside the valid positions while the others shift
+        self.table
+            .relocate(self.entries[from].hash, from, usize::MAX);
+        if from < to {
+            for position in from + 1..=to {
+                self.table
+                    .relocate(self.entries[position].hash, position, position - 1);
+            }
+            self.entries[from..=to].rotate_left(1);
+        } else {
+            for position in (to..from).rev() {
+                self.table
+                    .relocate(self.entries[position].hash, position, position + 1);
+            }
+            self.entries[to..=from].rotate_right(1);
+        }
+        self.table.relocate(self.entries[to].hash, usize::MAX, to);
+    }
+
+    /// Sorts the entries with a comparator, keeping equal entries in their order.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&K, &V, &K, &V) -> Ordering,
+    {
+        self.entries
+            .sort_by(|a, b| compare(&a.key, &a.value, &b.key, &b.value));
+        self.table
+            .rebuild(self.entries.iter().map(|element| element.hash));
+    }
+
+    /// Sorts the entries by key.
+    pub fn sort_keys(&mut self)
+    where
+        K: Ord,
+    {
+        self.sort_by(|a, _, b, _| a.cmp(b));
+    }
+
+    /// Keeps only the entries `f` returns `true` for, preserving their order.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        let before = self.entries.len();
+        self.entries
+            .retain_mut(|element| f(&element.key, &mut element.value));
+        if self.entries.len() != before {
+            self.table
+                .rebuild(self.entries.iter().map(|element| element.hash));
+        }
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for OrderedTrashMap<K, V, S> {
+    fn clone(&self) -> Self {
+        OrderedTrashMap {
+            entries: self.entries.clone(),
+            table: self.table.clone(),
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for OrderedTrashMap<K, V, S> {
+    /// Formats the entries as `{k: v, ...}` in insertion order.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher + Default> Default for OrderedTrashMap<K, V, S> {
+    fn default() -> Self {
+        OrderedTrashMap::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> PartialEq for OrderedTrashMap<K, V, S>
+where
+    K: Hash + Eq + PartialEq,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    /// Two maps are equal if they hold the same entries, in any order.
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V: Eq, S: BuildHasher> Eq for OrderedTrashMap<K, V, S> {}
+
+impl<K, Q, V, S> Index<&Q> for OrderedTrashMap<K, V, S>
+where
+    K: Hash + Eq + PartialEq,
+    Q: ?Sized + Hash + Equivalent<K>,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    /// Returns a reference to the value of a key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not present in the map.
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in OrderedTrashMap")
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> Extend<(K, V)> for OrderedTrashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher + Default> FromIterator<(K, V)>
+    for OrderedTrashMap<K, V, S>
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = OrderedTrashMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, const N: usize> From<[(K, V); N]> for OrderedTrashMap<K, V> {
+    fn from(entries: [(K, V); N]) -> Self {
+        OrderedTrashMap::from_iter(entries)
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a OrderedTrashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut OrderedTrashMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+impl<K, V, S> IntoIterator for OrderedTrashMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            inner: self.entries.into_iter(),
+        }
+    }
+}
+
+/// An iterator over the entries of an [`OrderedTrashMap`] in insertion order.
+pub struct Iter<'a, K, V> {
+    inner: slice::Iter<'a, Element<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|e| (&e.key, &e.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|e| (&e.key, &e.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An iterator over the keys of an [`OrderedTrashMap`] in insertion order.
+pub struct Keys<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Keys<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+impl<K, V> Clone for Keys<'_, K, V> {
+    fn clone(&self) -> Self {
+        Keys {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An iterator over the values of an [`OrderedTrashMap`] in insertion order.
+pub struct Values<'a, K, V> {
+    inner: Iter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<&'a V> {
+        self.inner.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Values<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+impl<K, V> Clone for Values<'_, K, V> {
+    fn clone(&self) -> Self {
+        Values {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// A mutable iterator over the entries of an [`OrderedTrashMap`] in insertion order.
+pub struct IterMut<'a, K, V> {
+    inner: slice::IterMut<'a, Element<K, V>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|e| (&e.key, &mut e.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|e| (&e.key, &mut e.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+/// An owning iterator over the entries of an [`OrderedTrashMap`] in insertion order.
+pub struct IntoIter<K, V> {
+    inner: vec::IntoIter<Element<K, V>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(Element::into_pair)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(Element::into_pair)
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
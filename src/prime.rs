@@ -0,0 +1,155 @@
+//! Prime bucket counts.
+//!
+//! Reducing hashes modulo a prime spreads keys evenly even when the hashes share
+//! factors, so the chained map always uses a prime number of buckets.
+
+/// Bucket counts the map grows through, each the smallest prime greater than twice the
+/// previous one.
+pub(crate) const BUCKET_PRIMES: [u64; 62] = [
+    3,
+    7,
+    17,
+    37,
+    79,
+    163,
+    331,
+    673,
+    1361,
+    2729,
+    5471,
+    10949,
+    21911,
+    43853,
+    87719,
+    175447,
+    350899,
+    701819,
+    1403641,
+    2807303,
+    5614657,
+    11229331,
+    22458671,
+    44917381,
+    89834777,
+    179669557,
+    359339171,
+    718678369,
+    1437356741,
+    2874713497,
+    5749427029,
+    11498854069,
+    22997708177,
+    45995416409,
+    91990832831,
+    183981665689,
+    367963331389,
+    735926662813,
+    1471853325643,
+    2943706651297,
+    5887413302609,
+    11774826605231,
+    23549653210463,
+    47099306420939,
+    94198612841897,
+    188397225683869,
+    376794451367743,
+    753588902735509,
+    1507177805471059,
+    3014355610942127,
+    6028711221884317,
+    12057422443768697,
+    24114844887537407,
+    48229689775074839,
+    96459379550149709,
+    192918759100299439,
+    385837518200598889,
+    771675036401197787,
+    1543350072802395601,
+    3086700145604791213,
+    6173400291209582429,
+    12346800582419164889,
+];
+
+const SMALL_PRIMES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    if modulus <= u32::MAX as u64 {
+        a * b % modulus
+    } else {
+        (a as u128 * b as u128 % modulus as u128) as u64
+    }
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1;
+    base %= modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Deterministic Miller-Rabin test for all 64 bit numbers.
+pub(crate) fn is_prime(number: u64) -> bool {
+    if number < 2 {
+        return false;
+    }
+    for prime in SMALL_PRIMES {
+        if number.is_multiple_of(prime) {
+            return number == prime;
+        }
+    }
+    if number < 37 * 37 {
+        return true;
+    }
+
+    let exponent = (number - 1) >> (number - 1).trailing_zeros();
+    // these bases are enough for every number below 2^32 ...
+    let bases: &[u64] = if number < 1 << 32 {
+        &[2, 7, 61]
+    } else {
+        // ... and these for every number below 2^64
+        &SMALL_PRIMES
+    };
+    'bases: for &base in bases {
+        let mut x = pow_mod(base, exponent, number);
+        if x == 1 || x == number - 1 {
+            continue;
+        }
+        let mut power = exponent;
+        while power < number - 1 {
+            x = mul_mod(x, x, number);
+            power <<= 1;
+            if x == number - 1 {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// Returns the smallest prime that is at least `number`, or `None` if it does not fit
+/// into a `usize`.
+pub(crate) fn next_prime(number: usize) -> Option<usize> {
+    let mut candidate = number.max(2);
+    while !is_prime(candidate as u64) {
+        candidate = candidate.checked_add(1)?;
+    }
+    Some(candidate)
+}
+
+/// Returns the smallest bucket count from the growth table that is at least `number`,
+/// falling back to [`next_prime`] when the table has no prime close enough above it.
+pub(crate) fn next_bucket_prime(number: usize) -> Option<usize> {
+    BUCKET_PRIMES
+        .iter()
+        .find(|&&prime| prime >= number as u64)
+        .filter(|&&prime| prime - number as u64 <= number as u64 / 8)
+        .and_then(|&prime| usize::try_from(prime).ok())
+        .or_else(|| next_prime(number))
+}
@@ -0,0 +1,47 @@
+use crate::{index::BucketIndexer, Bucket};
+
+/// How a [`TrashMap`](crate::TrashMap) moves its elements into a larger bucket array.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizeMode {
+    /// Every element is rehashed into the new bucket array as soon as the map grows.
+    #[default]
+    Immediate,
+    /// The old and the new bucket array coexist after the map grows. Every mutating
+    /// operation moves `buckets_per_op` old buckets over, and lookups consult both
+    /// arrays until the old one is drained. This spreads the cost of growing over many
+    /// operations instead of pausing for a full rehash.
+    ///
+    /// Each operation moves more buckets if that is needed to drain the old array
+    /// before enough insertions or removals pile up to resize again, so a resize never
+    /// has to finish the previous one at once.
+    ///
+    /// Only operations that take the map by `&mut` advance the migration, including
+    /// [`get_mut`](crate::TrashMap::get_mut) and [`entry`](crate::TrashMap::entry).
+    /// [`get`](crate::TrashMap::get) and [`contains_key`](crate::TrashMap::contains_key)
+    /// take `&self`, so a map that is only read keeps probing both arrays. Switch to
+    /// [`Immediate`](ResizeMode::Immediate) with
+    /// [`set_resize_mode`](crate::TrashMap::set_resize_mode) to finish the migration
+    /// without writes.
+    Incremental { buckets_per_op: usize },
+}
+
+/// The state of an incremental resize: the old bucket array, which is drained in order.
+#[derive(Clone, Debug)]
+pub(crate) struct Migration<K, V> {
+    pub(crate) buckets: Vec<Bucket<K, V>>,
+    pub(crate) indexer: BucketIndexer,
+    /// All old buckets before this index have been moved to the new array.
+    pub(crate) next: usize,
+    /// How many old buckets every operation moves over.
+    pub(crate) step: usize,
+}
+
+impl<K, V> Migration<K, V> {
+    pub(crate) fn remaining(&self) -> &[Bucket<K, V>] {
+        &self.buckets[self.next..]
+    }
+
+    pub(crate) fn remaining_mut(&mut self) -> &mut [Bucket<K, V>] {
+        &mut self.buckets[self.next..]
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::Equivalent;
+
+const ROBIN_HOOD_START_SIZE: usize = 8;
+const ROBIN_HOOD_LOAD_FACTOR_THRESH: f32 = 0.9;
+
+#[derive(Clone, Debug)]
+struct Slot<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+/// Probe length statistics of a [`RobinHoodMap`], measured over all occupied slots.
+///
+/// The probe length of an element is its distance from the slot its hash points to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProbeStats {
+    pub max: usize,
+    pub mean: f64,
+    pub variance: f64,
+}
+
+/// A hash map using open addressing with Robin Hood probing.
+///
+/// Elements live directly in a power of two sized slot array instead of bucket chains.
+/// On insertion an element takes the slot of any element that is closer to its own
+/// home slot, which keeps probe lengths short and evenly distributed even at high
+/// load factors. Removal shifts the following elements back instead of leaving
+/// tombstones.
+#[derive(Debug)]
+pub struct RobinHoodMap<K, V, S = RandomState> {
+    slots: Vec<Option<Slot<K, V>>>,
+    elements: usize,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq + PartialEq, V> RobinHoodMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        RobinHoodMap::with_hasher(RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V> Default for RobinHoodMap<K, V, RandomState> {
+    fn default() -> Self {
+        RobinHoodMap::new()
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> RobinHoodMap<K, V, S> {
+    fn make_slots(count: usize) -> Vec<Option<Slot<K, V>>> {
+        let mut slots = Vec::with_capacity(count);
+        slots.resize_with(count, || None);
+        slots
+    }
+
+    /// Creates an empty map which hashes its keys with `hash_builder`.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        RobinHoodMap {
+            slots: RobinHoodMap::<K, V, S>::make_slots(ROBIN_HOOD_START_SIZE),
+            elements: 0,
+            hash_builder,
+        }
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    fn probe_length(&self, hash: u64, index: usize) -> usize {
+        index.wrapping_sub(hash as usize) & self.mask()
+    }
+
+    fn find<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<usize> {
+        let hash = self.hash_builder.hash_one(key);
+        let mut index = hash as usize & self.mask();
+        let mut distance = 0;
+        while let Some(slot) = &self.slots[index] {
+            // an element closer to its home than we are to ours means the key is absent
+            if self.probe_length(slot.hash, index) < distance {
+                return None;
+            }
+            if slot.hash == hash && key.equivalent(&slot.key) {
+                return Some(index);
+            }
+            index = (index + 1) & self.mask();
+            distance += 1;
+        }
+        None
+    }
+
+    fn grow(&mut self) {
+        let new_slots = RobinHoodMap::<K, V, S>::make_slots(self.slots.len() * 2);
+        let old_slots = std::mem::replace(&mut self.slots, new_slots);
+        for slot in old_slots.into_iter().flatten() {
+            self.place(slot);
+        }
+    }
+
+    /// Places an element whose key is known to be absent from the map.
+    fn place(&mut self, mut slot: Slot<K, V>) {
+        let mask = self.mask();
+        let mut index = slot.hash as usize & mask;
+        let mut distance = 0;
+        loop {
+            match &mut self.slots[index] {
+                None => {
+                    self.slots[index] = Some(slot);
+                    return;
+                }
+                Some(existing) => {
+                    let existing_distance = index.wrapping_sub(existing.hash as usize) & mask;
+                    if existing_distance < distance {
+                        std::mem::swap(existing, &mut slot);
+                        distance = existing_distance;
+                    }
+                }
+            }
+            index = (index + 1) & mask;
+            distance += 1;
+        }
+    }
+
+    /// Inserts a key-value pair into the map, returning the previous value of the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(index) = self.find(&key) {
+            let slot = self.slots[index].as_mut().expect("found slot is occupied");
+            return Some(std::mem::replace(&mut slot.value, value));
+        }
+        let load_factor = (self.elements + 1) as f32 / self.slots.len() as f32;
+        if load_factor > ROBIN_HOOD_LOAD_FACTOR_THRESH {
+            self.grow();
+        }
+        let hash = self.hash_builder.hash_one(&key);
+        self.place(Slot { hash, key, value });
+        self.elements += 1;
+        None
+    }
+
+    pub fn get<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<&V> {
+        let index = self.find(key)?;
+        self.slots[index].as_ref().map(|slot| &slot.value)
+    }
+
+    pub fn contains_key<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<V> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes a key from the map, shifting the elements probed past it back by one.
+    pub fn remove_entry<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<(K, V)> {
+        let mut index = self.find(key)?;
+        let removed = self.slots[index].take().expect("found slot is occupied");
+        loop {
+            let next = (index + 1) & self.mask();
+            match &self.slots[next] {
+                Some(slot) if self.probe_length(slot.hash, next) > 0 => {
+                    self.slots[index] = self.slots[next].take();
+                    index = next;
+                }
+                _ => break,
+            }
+        }
+        self.elements -= 1;
+        Some((removed.key, removed.value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots
+            .iter()
+            .flatten()
+            .map(|slot| (&slot.key, &slot.value))
+    }
+
+    pub fn load_factor(&self) -> f32 {
+        self.elements as f32 / self.slots.len() as f32
+    }
+
+    pub fn probe_stats(&self) -> ProbeStats {
+        let lengths = || {
+            self.slots
+                .iter()
+                .enumerate()
+                .filter_map(|(index, slot)| Some(self.probe_length(slot.as_ref()?.hash, index)))
+        };
+        if self.elements == 0 {
+            return ProbeStats {
+                max: 0,
+                mean: 0.0,
+                variance: 0.0,
+            };
+        }
+        let count = self.elements as f64;
+        let mean = lengths().sum::<usize>() as f64 / count;
+        let variance = lengths()
+            .map(|length| (length as f64 - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        ProbeStats {
+            max: lengths().max().unwrap_or(0),
+            mean,
+            variance,
+        }
+    }
+}
@@ -0,0 +1,471 @@
+//! A hash set built on [`TrashMap`], and the iterators it returns.
+
+use std::{
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::{Chain, FusedIterator},
+    ops::{BitAnd, BitOr, BitXor, Sub},
+};
+
+use crate::{iter as map_iter, Equivalent, TrashMap};
+
+/// A hash set storing its values as the keys of a [`TrashMap`] with `()` values.
+///
+/// Growth, shrinking, hashing and bucket indexing all behave exactly like the
+/// underlying map.
+pub struct TrashSet<T, S = RandomState> {
+    map: TrashMap<T, (), S>,
+}
+
+impl<T: Hash + Eq + PartialEq> TrashSet<T, RandomState> {
+    pub fn new() -> Self {
+        TrashSet {
+            map: TrashMap::new(),
+        }
+    }
+
+    /// Creates an empty set which can hold at least `capacity` values without growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        TrashSet {
+            map: TrashMap::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T, S> TrashSet<T, S> {
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<T: Hash + Eq + PartialEq, S: BuildHasher> TrashSet<T, S> {
+    /// Creates an empty set which hashes its values with `hash_builder`.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        TrashSet {
+            map: TrashMap::with_hasher(hash_builder),
+        }
+    }
+
+    /// Creates an empty set which can hold at least `capacity` values without growing
+    /// and hashes its values with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        TrashSet {
+            map: TrashMap::with_capacity_and_hasher(capacity, hash_builder),
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        self.map.hasher()
+    }
+
+    /// Returns the number of values the set can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.map.reserve(additional)
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.map.shrink_to_fit()
+    }
+
+    /// Adds a value to the set, returning whether it was newly inserted. An equal value
+    /// that is already present is kept.
+    pub fn insert(&mut self, value: T) -> bool {
+        match self.map.entry(value) {
+            crate::Entry::Occupied(_) => false,
+            crate::Entry::Vacant(entry) => {
+                entry.insert(());
+                true
+            }
+        }
+    }
+
+    /// Adds a value to the set, replacing and returning an equal value that was
+    /// already present.
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        if let Some(element) = self.map.find_mut(&value) {
+            return Some(std::mem::replace(&mut element.key, value));
+        }
+        self.map.insert(value, ());
+        None
+    }
+
+    pub fn contains<Q: ?Sized + Hash + Equivalent<T>>(&self, value: &Q) -> bool {
+        self.map.contains_key(value)
+    }
+
+    /// Returns a reference to the stored value equal to `value`.
+    pub fn get<Q: ?Sized + Hash + Equivalent<T>>(&self, value: &Q) -> Option<&T> {
+        self.map.get_key_value(value).map(|(key, _)| key)
+    }
+
+    /// Removes a value from the set, returning whether it was present.
+    pub fn remove<Q: ?Sized + Hash + Equivalent<T>>(&mut self, value: &Q) -> bool {
+        self.map.remove(value).is_some()
+    }
+
+    /// Removes a value from the set, returning the stored value if it was present.
+    pub fn take<Q: ?Sized + Hash + Equivalent<T>>(&mut self, value: &Q) -> Option<T> {
+        self.map.remove_entry(value).map(|(key, _)| key)
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Removes all values, returning them as an iterator.
+    pub fn drain(&mut self) -> Drain<'_, T, S> {
+        Drain {
+            inner: self.map.drain(),
+        }
+    }
+
+    /// Keeps only the values `f` returns `true` for.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.map.retain(|key, _| f(key))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.map.keys(),
+        }
+    }
+
+    /// Returns an iterator over the values in `self` or `other`, without duplicates.
+    pub fn union<'a>(&'a self, other: &'a TrashSet<T, S>) -> Union<'a, T, S> {
+        let (larger, smaller) = if self.len() >= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Union {
+            iter: larger.iter().chain(smaller.difference(larger)),
+        }
+    }
+
+    /// Returns an iterator over the values in both `self` and `other`.
+    pub fn intersection<'a>(&'a self, other: &'a TrashSet<T, S>) -> Intersection<'a, T, S> {
+        // walk the smaller set and look the values up in the larger one
+        let (smaller, larger) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        Intersection {
+            iter: smaller.iter(),
+            other: larger,
+        }
+    }
+
+    /// Returns an iterator over the values in `self` but not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a TrashSet<T, S>) -> Difference<'a, T, S> {
+        Difference {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Returns an iterator over the values in exactly one of `self` and `other`.
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a TrashSet<T, S>,
+    ) -> SymmetricDifference<'a, T, S> {
+        SymmetricDifference {
+            iter: self.difference(other).chain(other.difference(self)),
+        }
+    }
+
+    /// Returns whether every value of `self` is also in `other`.
+    pub fn is_subset(&self, other: &TrashSet<T, S>) -> bool {
+        self.len() <= other.len() && self.iter().all(|value| other.contains(value))
+    }
+
+    /// Returns whether every value of `other` is also in `self`.
+    pub fn is_superset(&self, other: &TrashSet<T, S>) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns whether `self` and `other` have no values in common.
+    pub fn is_disjoint(&self, other: &TrashSet<T, S>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+}
+
+impl<T: Clone, S: Clone> Clone for TrashSet<T, S> {
+    fn clone(&self) -> Self {
+        TrashSet {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for TrashSet<T, S> {
+    /// Formats the values as `{a, b, ...}` in iteration order.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.map.iter().map(|(key, _)| key))
+            .finish()
+    }
+}
+
+impl<T: Hash + Eq + PartialEq, S: BuildHasher + Default> Default for TrashSet<T, S> {
+    fn default() -> Self {
+        TrashSet {
+            map: TrashMap::default(),
+        }
+    }
+}
+
+impl<T: Hash + Eq + PartialEq, S: BuildHasher> PartialEq for TrashSet<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T: Hash + Eq + PartialEq, S: BuildHasher> Eq for TrashSet<T, S> {}
+
+impl<T: Hash + Eq + PartialEq, S: BuildHasher> Extend<T> for TrashSet<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.map.extend(iter.into_iter().map(|value| (value, ())));
+    }
+}
+
+impl<'a, T: Hash + Eq + PartialEq + Copy + 'a, S: BuildHasher> Extend<&'a T> for TrashSet<T, S> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T: Hash + Eq + PartialEq, S: BuildHasher + Default> FromIterator<T> for TrashSet<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = TrashSet::default();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Hash + Eq + PartialEq, const N: usize> From<[T; N]> for TrashSet<T> {
+    fn from(values: [T; N]) -> Self {
+        TrashSet::from_iter(values)
+    }
+}
+
+impl<'a, T: Hash + Eq + PartialEq, S: BuildHasher> IntoIterator for &'a TrashSet<T, S> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, S> IntoIterator for TrashSet<T, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            inner: self.map.into_iter(),
+        }
+    }
+}
+
+impl<T, S> BitOr<&TrashSet<T, S>> for &TrashSet<T, S>
+where
+    T: Hash + Eq + PartialEq + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = TrashSet<T, S>;
+
+    /// Returns the union of `self` and `rhs` as a new set.
+    fn bitor(self, rhs: &TrashSet<T, S>) -> TrashSet<T, S> {
+        self.union(rhs).cloned().collect()
+    }
+}
+
+impl<T, S> BitAnd<&TrashSet<T, S>> for &TrashSet<T, S>
+where
+    T: Hash + Eq + PartialEq + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = TrashSet<T, S>;
+
+    /// Returns the intersection of `self` and `rhs` as a new set.
+    fn bitand(self, rhs: &TrashSet<T, S>) -> TrashSet<T, S> {
+        self.intersection(rhs).cloned().collect()
+    }
+}
+
+impl<T, S> Sub<&TrashSet<T, S>> for &TrashSet<T, S>
+where
+    T: Hash + Eq + PartialEq + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = TrashSet<T, S>;
+
+    /// Returns the difference of `self` and `rhs` as a new set.
+    fn sub(self, rhs: &TrashSet<T, S>) -> TrashSet<T, S> {
+        self.difference(rhs).cloned().collect()
+    }
+}
+
+impl<T, S> BitXor<&TrashSet<T, S>> for &TrashSet<T, S>
+where
+    T: Hash + Eq + PartialEq + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = TrashSet<T, S>;
+
+    /// Returns the symmetric difference of `self` and `rhs` as a new set.
+    fn bitxor(self, rhs: &TrashSet<T, S>) -> TrashSet<T, S> {
+        self.symmetric_difference(rhs).cloned().collect()
+    }
+}
+
+/// An iterator over the values of a [`TrashSet`], created by [`TrashSet::iter`].
+pub struct Iter<'a, T> {
+    inner: map_iter::Keys<'a, T, ()>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// An owning iterator over the values of a [`TrashSet`], created by its
+/// [`IntoIterator`] implementation.
+pub struct IntoIter<T> {
+    inner: map_iter::IntoIter<T, ()>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+/// A draining iterator over the values of a [`TrashSet`], created by
+/// [`TrashSet::drain`].
+pub struct Drain<'a, T, S = RandomState> {
+    inner: map_iter::Drain<'a, T, (), S>,
+}
+
+impl<T, S> Iterator for Drain<'_, T, S> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next().map(|(key, _)| key)
+    }
+}
+
+/// A lazy iterator over the union of two sets, created by [`TrashSet::union`].
+pub struct Union<'a, T, S = RandomState> {
+    iter: Chain<Iter<'a, T>, Difference<'a, T, S>>,
+}
+
+impl<'a, T: Hash + Eq + PartialEq, S: BuildHasher> Iterator for Union<'a, T, S> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// A lazy iterator over the intersection of two sets, created by
+/// [`TrashSet::intersection`].
+pub struct Intersection<'a, T, S = RandomState> {
+    iter: Iter<'a, T>,
+    other: &'a TrashSet<T, S>,
+}
+
+impl<'a, T: Hash + Eq + PartialEq, S: BuildHasher> Iterator for Intersection<'a, T, S> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let other = self.other;
+        self.iter.find(|value| other.contains(*value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// A lazy iterator over the values of one set that are not in another, created by
+/// [`TrashSet::difference`].
+pub struct Difference<'a, T, S = RandomState> {
+    iter: Iter<'a, T>,
+    other: &'a TrashSet<T, S>,
+}
+
+impl<'a, T: Hash + Eq + PartialEq, S: BuildHasher> Iterator for Difference<'a, T, S> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let other = self.other;
+        self.iter.find(|value| !other.contains(*value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// A lazy iterator over the values in exactly one of two sets, created by
+/// [`TrashSet::symmetric_difference`].
+pub struct SymmetricDifference<'a, T, S = RandomState> {
+    iter: Chain<Difference<'a, T, S>, Difference<'a, T, S>>,
+}
+
+impl<'a, T: Hash + Eq + PartialEq, S: BuildHasher> Iterator for SymmetricDifference<'a, T, S> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
@@ -0,0 +1,573 @@
+//! A SwissTable-style open addressing map and its entry API.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{
+    group::{is_full, BitMask, Group, DELETED, EMPTY, GROUP_WIDTH},
+    Equivalent, GetManyMutError, TryReserveError,
+};
+
+const SWISS_MAP_START_SIZE: usize = GROUP_WIDTH;
+
+/// The position of an element in the slot array, taken from the low bits of its hash.
+fn h1(hash: u64) -> usize {
+    hash as usize
+}
+
+/// The control byte of an element, taken from the top 7 bits of its hash.
+fn h2(hash: u64) -> u8 {
+    (hash >> 57) as u8
+}
+
+/// Number of elements a table with `buckets` slots holds before it needs to grow,
+/// which keeps the load factor at 7/8.
+fn bucket_capacity(buckets: usize) -> usize {
+    buckets / 8 * 7
+}
+
+/// Number of slots needed to hold `capacity` elements, or `None` on overflow.
+fn capacity_to_buckets(capacity: usize) -> Option<usize> {
+    let adjusted = capacity.checked_mul(8)? / 7 + 1;
+    Some(
+        adjusted
+            .checked_next_power_of_two()?
+            .max(SWISS_MAP_START_SIZE),
+    )
+}
+
+/// Triangular probing over whole groups, which visits every group exactly once when
+/// the number of slots is a power of two.
+struct ProbeSeq {
+    pos: usize,
+    stride: usize,
+}
+
+impl ProbeSeq {
+    fn move_next(&mut self, mask: usize) {
+        self.stride += GROUP_WIDTH;
+        self.pos = (self.pos + self.stride) & mask;
+    }
+}
+
+/// A hash map in the style of SwissTable.
+///
+/// Next to the slot array the map keeps one control byte per slot which is either
+/// `EMPTY`, `DELETED` or the top 7 bits of the hash of the element stored there.
+/// Lookups scan the control bytes a group of 16 at a time, using SSE2 where available,
+/// and only compare keys for slots whose control byte matches. Removed elements leave
+/// tombstones behind which are cleared by rehashing the table in place once they eat
+/// up the remaining room.
+#[derive(Debug)]
+pub struct SwissMap<K, V, S = RandomState> {
+    /// One byte per slot, followed by a copy of the first group so that groups can be
+    /// loaded across the end of the table.
+    ctrl: Vec<u8>,
+    slots: Vec<Option<(K, V)>>,
+    elements: usize,
+    growth_left: usize,
+    hash_builder: S,
+}
+
+impl<K: Hash + Eq + PartialEq, V> SwissMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        SwissMap::with_hasher(RandomState::new())
+    }
+
+    /// Creates an empty map which can hold at least `capacity` elements without growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        SwissMap::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher + Default> Default for SwissMap<K, V, S> {
+    fn default() -> Self {
+        SwissMap::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq + PartialEq, V, S: BuildHasher> SwissMap<K, V, S> {
+    fn with_buckets(buckets: usize, hash_builder: S) -> Self {
+        let mut slots = Vec::with_capacity(buckets);
+        slots.resize_with(buckets, || None);
+        SwissMap {
+            ctrl: vec![EMPTY; buckets + GROUP_WIDTH],
+            slots,
+            elements: 0,
+            growth_left: bucket_capacity(buckets),
+            hash_builder,
+        }
+    }
+
+    /// Creates an empty map which hashes its keys with `hash_builder`.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        SwissMap::with_buckets(SWISS_MAP_START_SIZE, hash_builder)
+    }
+
+    /// Creates an empty map which can hold at least `capacity` elements without growing
+    /// and hashes its keys with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let buckets = capacity_to_buckets(capacity).expect("capacity overflow");
+        SwissMap::with_buckets(buckets, hash_builder)
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    fn probe_seq(&self, hash: u64) -> ProbeSeq {
+        ProbeSeq {
+            pos: h1(hash) & self.mask(),
+            stride: 0,
+        }
+    }
+
+    fn group_at(&self, pos: usize) -> Group {
+        Group::load(&self.ctrl[pos..])
+    }
+
+    /// Sets a control byte, keeping the mirrored copy of the first group in sync.
+    fn set_ctrl(&mut self, index: usize, ctrl: u8) {
+        let mirror = (index.wrapping_sub(GROUP_WIDTH) & self.mask()) + GROUP_WIDTH;
+        self.ctrl[index] = ctrl;
+        self.ctrl[mirror] = ctrl;
+    }
+
+    fn find<Q: ?Sized + Equivalent<K>>(&self, hash: u64, key: &Q) -> Option<usize> {
+        let h2 = h2(hash);
+        let mask = self.mask();
+        let mut probe = self.probe_seq(hash);
+        loop {
+            let group = self.group_at(probe.pos);
+            for bit in group.match_byte(h2) {
+                let index = (probe.pos + bit) & mask;
+                if let Some((candidate, _)) = &self.slots[index] {
+                    if key.equivalent(candidate) {
+                        return Some(index);
+                    }
+                }
+            }
+            if group.match_empty().any() {
+                return None;
+            }
+            probe.move_next(mask);
+        }
+    }
+
+    /// Finds the first empty or deleted slot on the probe sequence of `hash`.
+    fn find_insert_slot(&self, hash: u64) -> usize {
+        let mask = self.mask();
+        let mut probe = self.probe_seq(hash);
+        loop {
+            if let Some(bit) = self.group_at(probe.pos).match_empty_or_deleted().lowest() {
+                return (probe.pos + bit) & mask;
+            }
+            probe.move_next(mask);
+        }
+    }
+
+    /// Makes room for `additional` more elements, either by clearing tombstones in
+    /// place or by moving everything into a larger table.
+    fn reserve_rehash(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .elements
+            .checked_add(additional)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        let full_capacity = bucket_capacity(self.slots.len());
+        if required <= full_capacity / 2 {
+            self.rehash_in_place();
+            Ok(())
+        } else {
+            self.resize(required.max(full_capacity + 1))
+        }
+    }
+
+    fn resize(&mut self, capacity: usize) -> Result<(), TryReserveError> {
+        let buckets = capacity_to_buckets(capacity).ok_or(TryReserveError::CapacityOverflow)?;
+        let mut slots = Vec::new();
+        slots
+            .try_reserve_exact(buckets)
+            .map_err(|_| TryReserveError::AllocError)?;
+        slots.resize_with(buckets, || None);
+        let old_slots = std::mem::replace(&mut self.slots, slots);
+        self.ctrl = vec![EMPTY; buckets + GROUP_WIDTH];
+        self.growth_left = bucket_capacity(buckets) - self.elements;
+        for (key, value) in old_slots.into_iter().flatten() {
+            let hash = self.hash_builder.hash_one(&key);
+            let index = self.find_insert_slot(hash);
+            self.set_ctrl(index, h2(hash));
+            self.slots[index] = Some((key, value));
+        }
+        Ok(())
+    }
+
+    /// Rehashes all elements without allocating, turning every tombstone back into an
+    /// empty slot.
+    ///
+    /// All full slots are first marked as deleted, meaning "still to be placed", and
+    /// all tombstones as empty. Each pending element then either stays where it is, if
+    /// it already sits in the first group it would probe, or is moved to the first
+    /// free slot of its probe sequence. When that slot holds another pending element,
+    /// the two are swapped and the displaced one is placed next.
+    fn rehash_in_place(&mut self) {
+        let buckets = self.slots.len();
+        let mask = self.mask();
+        for ctrl in &mut self.ctrl[..buckets] {
+            *ctrl = if is_full(*ctrl) { DELETED } else { EMPTY };
+        }
+        self.ctrl.copy_within(..GROUP_WIDTH, buckets);
+
+        for index in 0..buckets {
+            if self.ctrl[index] != DELETED {
+                continue;
+            }
+            loop {
+                let (key, _) = self.slots[index]
+                    .as_ref()
+                    .expect("pending slot is occupied");
+                let hash = self.hash_builder.hash_one(key);
+                let new_index = self.find_insert_slot(hash);
+                let start = h1(hash) & mask;
+                let probe_group = |pos: usize| (pos.wrapping_sub(start) & mask) / GROUP_WIDTH;
+                if probe_group(index) == probe_group(new_index) {
+                    self.set_ctrl(index, h2(hash));
+                    break;
+                }
+                let previous = self.ctrl[new_index];
+                self.set_ctrl(new_index, h2(hash));
+                self.slots.swap(index, new_index);
+                if previous == EMPTY {
+                    self.set_ctrl(index, EMPTY);
+                    break;
+                }
+                // the swapped in element is still pending, place it next
+            }
+        }
+        self.growth_left = bucket_capacity(buckets) - self.elements;
+    }
+
+    /// Returns the number of elements the map can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.elements + self.growth_left
+    }
+
+    /// Reserves room for at least `additional` more elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows the slot array.
+    pub fn reserve(&mut self, additional: usize) {
+        self.try_reserve(additional).expect("capacity overflow");
+    }
+
+    /// Like [`reserve`](SwissMap::reserve), but returns an error instead of panicking.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        if additional > self.growth_left {
+            self.reserve_rehash(additional)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Shrinks the slot array as much as possible while keeping the load factor below
+    /// its threshold.
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(0);
+    }
+
+    /// Shrinks the slot array so it can still hold at least `min_capacity` elements
+    /// without growing. Does nothing if the map is already smaller.
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let required = self.elements.max(min_capacity);
+        let buckets = capacity_to_buckets(required).expect("capacity overflow");
+        if buckets < self.slots.len() {
+            self.resize(required).expect("shrinking never overflows");
+        }
+    }
+
+    /// Inserts a key-value pair into the map, returning the previous value of the key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hash_builder.hash_one(&key);
+        if let Some(index) = self.find(hash, &key) {
+            let (_, existing) = self.slots[index].as_mut().expect("found slot is occupied");
+            return Some(std::mem::replace(existing, value));
+        }
+        self.insert_new(hash, key, value);
+        None
+    }
+
+    /// Stores a key that is not in the map yet, growing the table if there is no room
+    /// left. Returns the slot it went into.
+    fn insert_new(&mut self, hash: u64, key: K, value: V) -> usize {
+        let mut index = self.find_insert_slot(hash);
+        // reusing a tombstone does not take away from the room left to grow
+        if self.growth_left == 0 && self.ctrl[index] == EMPTY {
+            self.reserve_rehash(1).expect("capacity overflow");
+            index = self.find_insert_slot(hash);
+        }
+        if self.ctrl[index] == EMPTY {
+            self.growth_left -= 1;
+        }
+        self.set_ctrl(index, h2(hash));
+        self.slots[index] = Some((key, value));
+        self.elements += 1;
+        index
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.find(hash, &key) {
+            Some(index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+            None => Entry::Vacant(VacantEntry {
+                map: self,
+                hash,
+                key,
+            }),
+        }
+    }
+
+    pub fn get<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> Option<&V> {
+        let index = self.find(self.hash_builder.hash_one(key), key)?;
+        self.slots[index].as_ref().map(|(_, value)| value)
+    }
+
+    pub fn get_mut<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<&mut V> {
+        let index = self.find(self.hash_builder.hash_one(key), key)?;
+        self.slots[index].as_mut().map(|(_, value)| value)
+    }
+
+    pub fn contains_key<Q: ?Sized + Hash + Equivalent<K>>(&self, key: &Q) -> bool {
+        self.find(self.hash_builder.hash_one(key), key).is_some()
+    }
+
+    /// Returns mutable references to the values of several keys at once.
+    ///
+    /// Keys that are not present yield `None`. If two keys refer to the same entry,
+    /// [`GetManyMutError`] is returned instead, since the references would alias.
+    pub fn get_many_mut<Q: ?Sized + Hash + Equivalent<K>, const N: usize>(
+        &mut self,
+        keys: [&Q; N],
+    ) -> Result<[Option<&mut V>; N], GetManyMutError> {
+        let indices = keys.map(|key| self.find(self.hash_builder.hash_one(key), key));
+        for (i, index) in indices.iter().enumerate() {
+            if index.is_some() && indices[..i].contains(index) {
+                return Err(GetManyMutError);
+            }
+        }
+
+        // split every found slot off the front of the slots left over, lowest index
+        // first, so the borrow checker sees that the references do not overlap
+        let mut order: [usize; N] = std::array::from_fn(|i| i);
+        order.sort_unstable_by_key(|&i| indices[i]);
+        let mut values: [Option<&mut V>; N] = std::array::from_fn(|_| None);
+        let mut rest = self.slots.as_mut_slice();
+        let mut offset = 0;
+        for i in order {
+            let Some(index) = indices[i] else {
+                continue;
+            };
+            let (slot, tail) = std::mem::take(&mut rest)[index - offset..]
+                .split_first_mut()
+                .expect("slot index out of bounds");
+            rest = tail;
+            offset = index + 1;
+            values[i] = slot.as_mut().map(|(_, value)| value);
+        }
+        Ok(values)
+    }
+
+    pub fn remove<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<V> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes a key from the map, returning the stored key and value if it was present.
+    pub fn remove_entry<Q: ?Sized + Hash + Equivalent<K>>(&mut self, key: &Q) -> Option<(K, V)> {
+        let index = self.find(self.hash_builder.hash_one(key), key)?;
+        Some(self.remove_at(index))
+    }
+
+    /// Empties the slot at `index`.
+    ///
+    /// The slot only becomes a tombstone if a probe sequence could have passed over it
+    /// while the surrounding groups were full; otherwise it is marked empty again.
+    fn remove_at(&mut self, index: usize) -> (K, V) {
+        let index_before = index.wrapping_sub(GROUP_WIDTH) & self.mask();
+        let empty_before: BitMask = self.group_at(index_before).match_empty();
+        let empty_after: BitMask = self.group_at(index).match_empty();
+        let ctrl = if empty_before.leading_zeros() + empty_after.trailing_zeros() >= GROUP_WIDTH {
+            DELETED
+        } else {
+            self.growth_left += 1;
+            EMPTY
+        };
+        self.set_ctrl(index, ctrl);
+        self.elements -= 1;
+        self.slots[index].take().expect("removed slot is occupied")
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements == 0
+    }
+
+    /// Returns the number of tombstones left behind by removals.
+    pub fn tombstones(&self) -> usize {
+        self.ctrl[..self.slots.len()]
+            .iter()
+            .filter(|&&ctrl| ctrl == DELETED)
+            .count()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots.iter().flatten().map(|(key, value)| (key, value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.slots
+            .iter_mut()
+            .flatten()
+            .map(|(key, value)| (&*key, value))
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+}
+
+/// A view into a single entry of a [`SwissMap`], which is either occupied or vacant.
+///
+/// Created by [`SwissMap::entry`]. The slot is found once when the entry is created,
+/// so modifying through it does not probe again and inserting does not hash the key
+/// again.
+pub enum Entry<'a, K, V, S = RandomState> {
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+/// An entry whose key is already present in the map.
+pub struct OccupiedEntry<'a, K, V, S = RandomState> {
+    map: &'a mut SwissMap<K, V, S>,
+    index: usize,
+}
+
+/// An entry whose key is not present in the map.
+pub struct VacantEntry<'a, K, V, S = RandomState> {
+    map: &'a mut SwissMap<K, V, S>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K: Hash + Eq + PartialEq, V, S: BuildHasher> Entry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, K: Hash + Eq + PartialEq, V, S: BuildHasher> OccupiedEntry<'a, K, V, S> {
+    fn pair(&self) -> &(K, V) {
+        self.map.slots[self.index]
+            .as_ref()
+            .expect("entry slot is occupied")
+    }
+
+    pub fn key(&self) -> &K {
+        &self.pair().0
+    }
+
+    pub fn get(&self) -> &V {
+        &self.pair().1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        let (_, value) = self.map.slots[self.index]
+            .as_mut()
+            .expect("entry slot is occupied");
+        value
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        let (_, value) = self.map.slots[self.index]
+            .as_mut()
+            .expect("entry slot is occupied");
+        value
+    }
+
+    /// Replaces the value of the entry, returning the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.map.remove_at(self.index)
+    }
+}
+
+impl<'a, K: Hash + Eq + PartialEq, V, S: BuildHasher> VacantEntry<'a, K, V, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Inserts the key with the given value, returning a mutable reference to the value.
+    ///
+    /// If the table has no room left, it grows or clears its tombstones first and the
+    /// slot is found again from the hash computed by [`SwissMap::entry`].
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+        let index = map.insert_new(self.hash, self.key, value);
+        let (_, value) = map.slots[index]
+            .as_mut()
+            .expect("inserted slot is occupied");
+        value
+    }
+}
@@ -0,0 +1,125 @@
+//! A chained hash table that maps hashes to positions in external storage.
+
+use crate::{
+    chain::{Chain, Element},
+    index::BucketIndexer,
+    BucketIndexing, GrowthPolicy,
+};
+
+/// Separate chaining over positions instead of key-value pairs, for maps that keep
+/// their entries somewhere else, e.g. in a `Vec` ordered by insertion.
+///
+/// Every slot stores the full hash of its entry next to the position, so lookups only
+/// look at entries whose hash matches and the table grows without touching the
+/// entries at all. Since the table never sees the keys, callers pass an `eq` closure
+/// to decide whether the entry at a position is the one they are looking for.
+#[derive(Clone, Debug)]
+pub(crate) struct IndexTable {
+    buckets: Vec<Chain<usize, ()>>,
+    indexer: BucketIndexer,
+    policy: GrowthPolicy,
+    len: usize,
+}
+
+impl IndexTable {
+    /// Creates a table which can hold at least `capacity` positions without growing.
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let policy = GrowthPolicy::default();
+        let indexing = BucketIndexing::default();
+        let needed = (capacity as f64 / policy.max_load_factor() as f64).ceil() as usize;
+        let count = indexing
+            .bucket_count(needed.max(policy.min_buckets()))
+            .expect("capacity overflow");
+        IndexTable {
+            buckets: IndexTable::make_buckets(count),
+            indexer: BucketIndexer::new(indexing, count),
+            policy,
+            len: 0,
+        }
+    }
+
+    fn make_buckets(count: usize) -> Vec<Chain<usize, ()>> {
+        (0..count).map(|_| Chain::new()).collect()
+    }
+
+    /// Returns the position of the entry with `hash` that `eq` accepts.
+    pub(crate) fn find(&self, hash: u64, mut eq: impl FnMut(usize) -> bool) -> Option<usize> {
+        self.buckets[self.indexer.index(hash)]
+            .iter()
+            .find(|slot| slot.hash == hash && eq(slot.key))
+            .map(|slot| slot.key)
+    }
+
+    /// Records that the entry with `hash` lives at `position`, growing the table if it
+    /// gets too full. The caller makes sure the entry is not in the table yet.
+    pub(crate) fn insert(&mut self, hash: u64, position: usize) {
+        let load_factor = (self.len + 1) as f32 / self.buckets.len() as f32;
+        if load_factor > self.policy.max_load_factor() {
+            self.grow();
+        }
+        self.buckets[self.indexer.index(hash)].push(Element {
+            hash,
+            key: position,
+            value: (),
+        });
+        self.len += 1;
+    }
+
+    /// Forgets the entry with `hash` at `position`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such entry.
+    pub(crate) fn remove(&mut self, hash: u64, position: usize) {
+        let chain = &mut self.buckets[self.indexer.index(hash)];
+        let index = chain
+            .iter()
+            .position(|slot| slot.hash == hash && slot.key == position)
+            .expect("position is not in the table");
+        chain.swap_remove(index);
+        self.len -= 1;
+    }
+
+    /// Records that the entry with `hash` moved from position `from` to `to`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no entry at `from`.
+    pub(crate) fn relocate(&mut self, hash: u64, from: usize, to: usize) {
+        let slot = self.buckets[self.indexer.index(hash)]
+            .iter_mut()
+            .find(|slot| slot.hash == hash && slot.key == from)
+            .expect("position is not in the table");
+        slot.key = to;
+    }
+
+    /// Forgets all entries, keeping the bucket array.
+    pub(crate) fn clear(&mut self) {
+        for chain in &mut self.buckets {
+            *chain = Chain::new();
+        }
+        self.len = 0;
+    }
+
+    /// Replaces the contents of the table with one entry per hash, at the position of
+    /// the hash in `hashes`. Used after the entries were reordered wholesale.
+    pub(crate) fn rebuild(&mut self, hashes: impl IntoIterator<Item = u64>) {
+        self.clear();
+        for (position, hash) in hashes.into_iter().enumerate() {
+            self.insert(hash, position);
+        }
+    }
+
+    fn grow(&mut self) {
+        let count = self
+            .indexer
+            .indexing()
+            .grown_bucket_count(self.buckets.len(), self.policy.growth_factor())
+            .expect("capacity overflow");
+        self.indexer = BucketIndexer::new(self.indexer.indexing(), count);
+        let old_buckets = std::mem::replace(&mut self.buckets, IndexTable::make_buckets(count));
+        for slot in old_buckets.into_iter().flatten() {
+            self.buckets[self.indexer.index(slot.hash)].push(slot);
+        }
+    }
+}
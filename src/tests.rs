@@ -1,4 +1,21 @@
-use crate::TrashMap;
+use std::{
+    cell::Cell,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, BuildHasherDefault, Hash, Hasher},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    group::{generic, Group, DELETED, EMPTY, GROUP_WIDTH},
+    index::BucketIndexer,
+    lru::Put,
+    prime,
+    swiss::Entry as SwissEntry,
+    BucketIndexing, Clock, Entry, Equivalent, ExpiringTrashMap, GetManyMutError, GrowthPolicy,
+    GrowthPolicyError, LruTrashMap, OrderedTrashMap, ResizeMode, RobinHoodMap, SwissMap, TrashMap,
+    TrashMultiMap, TrashSet, TryReserveError,
+};
 
 #[test]
 fn test_insert_collsions() {
@@ -7,8 +24,2051 @@ fn test_insert_collsions() {
         map.insert(i, ());
     }
     for i in 0..100 {
-        assert!(map.remove(&i));
+        assert_eq!(map.remove(&i), Some(()));
+    }
+    assert_eq!(map.remove(&0), None);
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_entry_api() {
+    let mut map: TrashMap<&str, i32> = TrashMap::new();
+    *map.entry("a").or_insert(1) += 10;
+    *map.entry("a").or_insert(1) += 10;
+    map.entry("b").and_modify(|v| *v = 100).or_default();
+    map.entry("b").and_modify(|v| *v += 5).or_insert_with(|| 7);
+    assert_eq!(map.get(&"a"), Some(&21));
+    assert_eq!(map.get(&"b"), Some(&5));
+    assert_eq!(map.len(), 2);
+
+    match map.entry("a") {
+        Entry::Occupied(entry) => {
+            assert_eq!(entry.key(), &"a");
+            assert_eq!(entry.remove_entry(), ("a", 21));
+        }
+        Entry::Vacant(_) => panic!("expected occupied entry"),
+    }
+    assert_eq!(map.get(&"a"), None);
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn test_entry_grows() {
+    let mut map = TrashMap::new();
+    for i in 0..1000 {
+        *map.entry(i % 500).or_insert(0) += 1;
+    }
+    assert_eq!(map.len(), 500);
+    for i in 0..500 {
+        assert_eq!(map.get(&i), Some(&2));
+    }
+}
+
+#[test]
+fn test_insert_returns_previous() {
+    let mut map = TrashMap::new();
+    assert_eq!(map.insert("key", 1), None);
+    assert_eq!(map.insert("key", 2), Some(1));
+    assert_eq!(map.insert("key", 3), Some(2));
+    assert_eq!(map.get(&"key"), Some(&3));
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn test_overwrite_does_not_grow() {
+    let mut map = TrashMap::new();
+    let buckets = map.buckets.len();
+    for i in 0..10_000 {
+        map.insert(0, i);
+    }
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.buckets.len(), buckets);
+
+    for i in 0..10 {
+        map.insert(i, 0);
+    }
+    let buckets = map.buckets.len();
+    for round in 0..1000 {
+        for i in 0..10 {
+            assert_eq!(map.insert(i, round + 1), Some(round));
+        }
+    }
+    assert_eq!(map.len(), 10);
+    assert_eq!(map.buckets.len(), buckets);
+}
+
+#[test]
+fn test_remove_returns_value() {
+    let mut map = TrashMap::new();
+    for i in 0..50 {
+        map.insert(i.to_string(), i * 2);
+    }
+    assert_eq!(map.remove(&"7".to_string()), Some(14));
+    assert_eq!(map.remove(&"7".to_string()), None);
+    assert_eq!(
+        map.remove_entry(&"8".to_string()),
+        Some(("8".to_string(), 16))
+    );
+    assert_eq!(map.len(), 48);
+    for i in (0..50).filter(|i| *i != 7 && *i != 8) {
+        assert_eq!(map.get(&i.to_string()), Some(&(i * 2)));
+    }
+}
+
+#[test]
+fn test_mutable_access() {
+    let mut map = TrashMap::new();
+    for i in 0..20 {
+        map.insert(i, i);
+    }
+    *map.get_mut(&3).unwrap() += 100;
+    assert_eq!(map.get(&3), Some(&103));
+    assert_eq!(map.get_mut(&20), None);
+
+    for (key, value) in map.iter_mut() {
+        *value += key;
+    }
+    for value in map.values_mut() {
+        *value *= 2;
+    }
+    assert_eq!(map.get(&3), Some(&212));
+    assert_eq!(map.get(&19), Some(&76));
+}
+
+#[test]
+fn test_get_many_mut() {
+    let mut map = TrashMap::new();
+    for i in 0..100 {
+        map.insert(i, i);
+    }
+    let [a, b, missing, c] = map.get_many_mut([&42, &7, &1000, &99]).unwrap();
+    std::mem::swap(a.unwrap(), b.unwrap());
+    assert!(missing.is_none());
+    *c.unwrap() = 0;
+    assert_eq!(map.get(&42), Some(&7));
+    assert_eq!(map.get(&7), Some(&42));
+    assert_eq!(map.get(&99), Some(&0));
+
+    assert_eq!(map.get_many_mut([&1, &2, &1]), Err(GetManyMutError));
+    assert!(map.get_many_mut([&1000, &1000]).is_ok());
+}
+
+#[test]
+fn test_borrowed_lookups() {
+    let mut map: TrashMap<String, usize> = TrashMap::new();
+    for word in ["alpha", "beta", "gamma", "delta"] {
+        map.insert(word.to_string(), word.len());
+    }
+    assert_eq!(map.get("beta"), Some(&4));
+    assert!(map.contains_key("gamma"));
+    assert!(!map.contains_key("epsilon"));
+    *map.get_mut("alpha").unwrap() = 0;
+    assert_eq!(map.get("alpha"), Some(&0));
+    assert_eq!(map.remove("delta"), Some(5));
+    assert_eq!(map.remove_entry("gamma"), Some(("gamma".to_string(), 5)));
+    assert_eq!(map.len(), 2);
+}
+
+#[derive(Hash)]
+struct PairRef<'a>(&'a str, u32);
+
+impl Equivalent<(String, u32)> for PairRef<'_> {
+    fn equivalent(&self, key: &(String, u32)) -> bool {
+        self.0 == key.0 && self.1 == key.1
+    }
+}
+
+#[test]
+fn test_equivalent_composite_keys() {
+    let mut map = TrashMap::new();
+    for i in 0..50 {
+        map.insert((format!("key{}", i), i), i * 3);
+    }
+    assert_eq!(map.get(&PairRef("key10", 10)), Some(&30));
+    assert_eq!(map.get(&PairRef("key10", 11)), None);
+    assert!(map.contains_key(&PairRef("key49", 49)));
+    assert_eq!(map.remove(&PairRef("key3", 3)), Some(9));
+    assert!(!map.contains_key(&PairRef("key3", 3)));
+}
+
+#[derive(Default)]
+struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 << 8) | *byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
+    }
+}
+
+#[test]
+fn test_custom_hasher() {
+    let mut map: TrashMap<u64, u64, BuildHasherDefault<IdentityHasher>> = TrashMap::default();
+    for i in 0..1000 {
+        map.insert(i, i * i);
+    }
+    for i in 0..1000 {
+        assert_eq!(map.get(&i), Some(&(i * i)));
+    }
+    // identity hashing places each key in the bucket of its own value
+    let buckets = map.buckets.len() as u64;
+    for (i, bucket) in map.buckets.iter().enumerate() {
+        for element in bucket.chain.iter() {
+            assert_eq!(element.key % buckets, i as u64);
+        }
+    }
+    assert_eq!(map.hasher().hash_one(7u64), 7);
+}
+
+#[test]
+fn test_with_capacity_and_hasher() {
+    let mut map = TrashMap::with_capacity_and_hasher(100, RandomState::new());
+    let buckets = map.buckets.len();
+    for i in 0..100 {
+        map.insert(i, ());
+    }
+    assert_eq!(map.buckets.len(), buckets);
+    assert_eq!(map.len(), 100);
+}
+
+#[test]
+fn test_with_capacity() {
+    let mut map = TrashMap::with_capacity(10_000);
+    assert!(map.capacity() >= 10_000);
+    let buckets = map.buckets.len();
+    for i in 0..10_000 {
+        map.insert(i, i);
+    }
+    assert_eq!(map.buckets.len(), buckets);
+}
+
+#[test]
+fn test_reserve_and_shrink() {
+    // only shrink explicitly
+    let mut map = TrashMap::builder().min_load_factor(0.0).build().unwrap();
+    for i in 0..10 {
+        map.insert(i, i);
+    }
+    map.reserve(5000);
+    assert!(map.capacity() >= 5010);
+    let buckets = map.buckets.len();
+    for i in 10..5010 {
+        map.insert(i, i);
+    }
+    assert_eq!(map.buckets.len(), buckets);
+
+    for i in 100..5010 {
+        map.remove(&i);
+    }
+    map.shrink_to(1000);
+    assert!(map.capacity() >= 1000);
+    assert!(map.buckets.len() < buckets);
+    map.shrink_to_fit();
+    assert!(map.capacity() >= 100);
+    assert!(map.capacity() < 1000);
+    for i in 0..100 {
+        assert_eq!(map.get(&i), Some(&i));
+    }
+    assert_eq!(map.len(), 100);
+}
+
+#[test]
+fn test_try_reserve() {
+    let mut map: TrashMap<u64, u64> = TrashMap::new();
+    assert_eq!(map.try_reserve(100), Ok(()));
+    assert!(map.capacity() >= 100);
+    map.insert(1, 1);
+    assert_eq!(
+        map.try_reserve(usize::MAX),
+        Err(TryReserveError::CapacityOverflow)
+    );
+    assert_eq!(
+        map.try_reserve(usize::MAX / 64),
+        Err(TryReserveError::CapacityOverflow)
+    );
+    assert_eq!(map.get(&1), Some(&1));
+}
+
+#[derive(Default)]
+struct ConstantHasher;
+
+impl Hasher for ConstantHasher {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, _bytes: &[u8]) {}
+}
+
+#[test]
+fn test_single_bucket_chain() {
+    let mut map: TrashMap<u32, u32, BuildHasherDefault<ConstantHasher>> = TrashMap::default();
+    for i in 0..200 {
+        assert_eq!(map.insert(i, i), None);
+    }
+    assert_eq!(
+        map.buckets.iter().filter(|b| !b.chain.is_empty()).count(),
+        1
+    );
+    for i in (0..200).step_by(3) {
+        assert_eq!(map.remove(&i), Some(i));
+    }
+    for i in 0..200 {
+        let expected = if i % 3 == 0 { None } else { Some(&i) };
+        assert_eq!(map.get(&i), expected);
+    }
+    assert_eq!(map.iter().count(), map.len());
+}
+
+#[test]
+fn test_robin_hood_map() {
+    let mut map = RobinHoodMap::new();
+    for i in 0..10_000 {
+        assert_eq!(map.insert(i, i * 2), None);
+    }
+    assert_eq!(map.insert(4, 0), Some(8));
+    assert_eq!(map.len(), 10_000);
+    for i in (0..10_000).step_by(2) {
+        assert_eq!(map.remove(&i), Some(if i == 4 { 0 } else { i * 2 }));
+    }
+    for i in 0..10_000 {
+        let expected = if i % 2 == 0 { None } else { Some(&(i * 2)) };
+        assert_eq!(map.get(&i), expected, "key {}", i);
+    }
+    assert_eq!(map.iter().count(), 5_000);
+    assert_eq!(map.remove(&0), None);
+}
+
+#[test]
+fn test_robin_hood_probe_variance() {
+    let mut map = RobinHoodMap::new();
+    let mut i = 0;
+    // fill up to just below the growth threshold
+    while map.load_factor() < 0.89 {
+        map.insert(i, ());
+        i += 1;
+    }
+    let stats = map.probe_stats();
+    assert!(stats.mean < 5.0, "{:?}", stats);
+    assert!(stats.variance < 25.0, "{:?}", stats);
+    assert!(stats.max < 64, "{:?}", stats);
+}
+
+#[test]
+fn test_swiss_map() {
+    let mut map = SwissMap::new();
+    for i in 0..10_000 {
+        assert_eq!(map.insert(i.to_string(), i), None);
+    }
+    assert_eq!(map.insert("43".to_string(), 0), Some(43));
+    assert_eq!(map.len(), 10_000);
+    for i in (0..10_000).filter(|i| i % 3 == 0) {
+        assert!(map.remove(i.to_string().as_str()).is_some());
+    }
+    for i in 0..10_000 {
+        let expected = match i {
+            43 => Some(&0),
+            i if i % 3 == 0 => None,
+            _ => Some(&i),
+        };
+        assert_eq!(map.get(i.to_string().as_str()), expected);
+    }
+    *map.get_mut("1").unwrap() += 1;
+    let [a, b] = map.get_many_mut(["1", "2"]).unwrap();
+    std::mem::swap(a.unwrap(), b.unwrap());
+    assert_eq!(map.get("1"), Some(&2));
+    assert_eq!(map.get("2"), Some(&2));
+    assert_eq!(map.get_many_mut(["1", "1"]), Err(GetManyMutError));
+    // keys in any slot order, with absent keys in between
+    let [a, b, c, d] = map.get_many_mut(["9998", "3", "4", "10"]).unwrap();
+    assert_eq!(b, None);
+    *a.unwrap() = 1;
+    *c.unwrap() = 2;
+    *d.unwrap() = 3;
+    assert_eq!(map.get("9998"), Some(&1));
+    assert_eq!(map.get("4"), Some(&2));
+    assert_eq!(map.get("10"), Some(&3));
+    assert_eq!(map.iter().count(), map.len());
+}
+
+#[test]
+fn test_swiss_map_clears_tombstones_in_place() {
+    // identity hashing packs the keys densely, so removals have to leave tombstones
+    let mut map: SwissMap<u64, u64, BuildHasherDefault<IdentityHasher>> =
+        SwissMap::with_capacity_and_hasher(200, BuildHasherDefault::default());
+    let capacity = map.capacity() as u64;
+    for i in 0..capacity {
+        map.insert(i, i);
+    }
+    for i in 0..capacity - 4 {
+        assert_eq!(map.remove(&i), Some(i));
+    }
+    assert!(map.tombstones() > 0);
+    assert!(map.capacity() < capacity as usize);
+
+    map.insert(capacity, capacity);
+    assert_eq!(map.tombstones(), 0);
+    assert_eq!(map.capacity() as u64, capacity);
+    assert_eq!(map.len(), 5);
+    for i in capacity - 4..=capacity {
+        assert_eq!(map.get(&i), Some(&i));
+    }
+}
+
+#[test]
+fn test_swiss_map_entry() {
+    let mut map = SwissMap::new();
+    for word in "a b a c b a".split(' ') {
+        *map.entry(word).or_insert(0) += 1;
+    }
+    assert_eq!(map.get("a"), Some(&3));
+    assert_eq!(map.get("b"), Some(&2));
+    assert_eq!(map.get("c"), Some(&1));
+
+    map.entry("c").and_modify(|count| *count *= 10).or_default();
+    map.entry("d").and_modify(|count| *count *= 10).or_default();
+    assert_eq!(map.get("c"), Some(&10));
+    assert_eq!(map.get("d"), Some(&0));
+
+    match map.entry("a") {
+        SwissEntry::Occupied(mut entry) => {
+            assert_eq!(entry.key(), &"a");
+            assert_eq!(entry.insert(7), 3);
+            assert_eq!(entry.remove_entry(), ("a", 7));
+        }
+        SwissEntry::Vacant(_) => panic!("expected occupied entry"),
+    }
+    assert_eq!(map.get("a"), None);
+    assert_eq!(map.len(), 3);
+
+    // vacant entries grow the table when it is full
+    let mut map = SwissMap::new();
+    for i in 0..1000 {
+        match map.entry(i) {
+            SwissEntry::Vacant(entry) => *entry.insert(i) += 1,
+            SwissEntry::Occupied(_) => panic!("expected vacant entry"),
+        }
+    }
+    assert_eq!(map.len(), 1000);
+    for i in 0..1000 {
+        assert_eq!(map.get(&i), Some(&(i + 1)));
+    }
+}
+
+#[test]
+fn test_swiss_map_shrink_to() {
+    let mut map = SwissMap::with_capacity(1000);
+    for i in 0..10 {
+        map.insert(i, i);
+    }
+    map.shrink_to(100);
+    assert!(map.capacity() >= 100);
+    assert!(map.capacity() < 1000);
+    // never below the elements it holds
+    map.shrink_to(0);
+    assert!(map.capacity() >= 10);
+    let capacity = map.capacity();
+    map.shrink_to(500);
+    assert_eq!(map.capacity(), capacity);
+    for i in 0..10 {
+        assert_eq!(map.get(&i), Some(&i));
+    }
+}
+
+#[test]
+fn test_group_matches_generic() {
+    let mut ctrl = [0u8; 64];
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    for byte in ctrl.iter_mut() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        *byte = match state % 4 {
+            0 => EMPTY,
+            1 => DELETED,
+            _ => (state >> 8) as u8 & 0x7f,
+        };
+    }
+    for start in 0..ctrl.len() - GROUP_WIDTH {
+        let group = Group::load(&ctrl[start..]);
+        let generic = generic::Group::load(&ctrl[start..]);
+        assert_eq!(group.match_empty(), generic.match_empty());
+        assert_eq!(
+            group.match_empty_or_deleted(),
+            generic.match_empty_or_deleted()
+        );
+        for byte in 0..0x80 {
+            assert_eq!(group.match_byte(byte), generic.match_byte(byte));
+        }
+    }
+}
+
+fn sieve(limit: usize) -> Vec<bool> {
+    let mut primes = vec![true; limit];
+    primes[0] = false;
+    primes[1] = false;
+    let mut i = 2;
+    while i * i < limit {
+        if primes[i] {
+            for multiple in (i * i..limit).step_by(i) {
+                primes[multiple] = false;
+            }
+        }
+        i += 1;
+    }
+    primes
+}
+
+#[test]
+fn test_is_prime_matches_sieve() {
+    let primes = sieve(3_000_000);
+    for (number, &expected) in primes.iter().enumerate() {
+        assert_eq!(prime::is_prime(number as u64), expected, "{}", number);
+    }
+    for square in [9, 25, 49, 121, 169, 289, 361, 529] {
+        assert!(!prime::is_prime(square));
+    }
+    assert!(prime::is_prime(2));
+}
+
+#[test]
+fn test_is_prime_large() {
+    // largest primes below 2^32 and 2^64
+    assert!(prime::is_prime(4_294_967_291));
+    assert!(prime::is_prime(18_446_744_073_709_551_557));
+    // strong pseudoprimes to several small bases
+    assert!(!prime::is_prime(3_215_031_751));
+    assert!(!prime::is_prime(3_825_123_056_546_413_051));
+    assert!(!prime::is_prime(4_294_967_291 * 4_294_967_279));
+}
+
+#[test]
+fn test_next_prime() {
+    let primes = sieve(100_000);
+    for number in 0..99_000 {
+        let expected = (number.max(2)..).find(|&n| primes[n]).unwrap();
+        assert_eq!(prime::next_prime(number), Some(expected));
+    }
+    assert_eq!(prime::next_prime(usize::MAX), None);
+}
+
+#[test]
+fn test_bucket_primes() {
+    for window in prime::BUCKET_PRIMES.windows(2) {
+        assert!(prime::is_prime(window[1]));
+        assert!(window[1] > window[0] * 2);
+        assert!(window[1] < window[0] * 2 + window[0] / 8 + 8);
+    }
+    assert_eq!(prime::next_bucket_prime(7), Some(7));
+    assert_eq!(prime::next_bucket_prime(1_400_000), Some(1_403_641));
+    assert_eq!(prime::next_bucket_prime(8), Some(11));
+}
+
+#[test]
+fn test_prime_modulo_indexing() {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    for &buckets in prime::BUCKET_PRIMES
+        .iter()
+        .filter(|&&p| p <= usize::MAX as u64)
+    {
+        let indexer = BucketIndexer::new(BucketIndexing::PrimeModulo, buckets as usize);
+        for hash in [
+            0,
+            1,
+            buckets - 1,
+            buckets,
+            buckets + 1,
+            u64::MAX,
+            u64::MAX - 1,
+        ] {
+            assert_eq!(indexer.index(hash) as u64, hash % buckets);
+        }
+        for _ in 0..1000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            assert_eq!(indexer.index(state) as u64, state % buckets);
+        }
+    }
+}
+
+#[test]
+fn test_bucket_indexing_strategies() {
+    for indexing in [
+        BucketIndexing::PrimeModulo,
+        BucketIndexing::PowerOfTwo,
+        BucketIndexing::FastRange,
+    ] {
+        let mut map = TrashMap::with_indexing(indexing);
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        if indexing == BucketIndexing::PowerOfTwo {
+            assert!(map.buckets.len().is_power_of_two());
+        }
+        for i in (0..10_000).step_by(2) {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        for i in 0..10_000 {
+            let expected = if i % 2 == 0 { None } else { Some(&i) };
+            assert_eq!(map.get(&i), expected);
+        }
+        assert_eq!(map.indexing(), indexing);
+        assert_eq!(map.len(), 5_000);
+    }
+}
+
+#[test]
+fn test_power_of_two_mixes_weak_hashes() {
+    // identity hashes that only differ in their high bits would all share the low bits
+    let mut map: TrashMap<u64, (), _> = TrashMap::with_hasher_and_indexing(
+        BuildHasherDefault::<IdentityHasher>::default(),
+        BucketIndexing::PowerOfTwo,
+    );
+    for i in 0..1000u64 {
+        map.insert(i << 32, ());
+    }
+    let used = map.buckets.iter().filter(|b| !b.chain.is_empty()).count();
+    assert!(
+        used > map.buckets.len() / 4,
+        "{} of {}",
+        used,
+        map.buckets.len()
+    );
+}
+
+#[test]
+fn test_incremental_resize() {
+    let mut map = TrashMap::new();
+    map.set_resize_mode(ResizeMode::Incremental { buckets_per_op: 2 });
+    let mut resizes = 0;
+    for i in 0..20_000 {
+        let was_resizing = map.is_resizing();
+        map.insert(i, i);
+        if !was_resizing && map.is_resizing() {
+            resizes += 1;
+            // growing only swaps the arrays, nothing is rehashed yet
+            let migration = map.migration.as_ref().unwrap();
+            assert_eq!(migration.next, 0);
+            assert_eq!(
+                migration
+                    .remaining()
+                    .iter()
+                    .map(|b| b.chain.len())
+                    .sum::<usize>()
+                    + map.buckets.iter().map(|b| b.chain.len()).sum::<usize>(),
+                map.len()
+            );
+        }
+        if let Some(migration) = &map.migration {
+            assert!(migration.next <= 2 * (i + 1));
+        }
+        assert_eq!(map.get(&(i / 2)), Some(&(i / 2)));
+    }
+    assert!(resizes > 5);
+    assert_eq!(map.len(), 20_000);
+    assert_eq!(map.iter().count(), 20_000);
+
+    for i in (0..20_000).step_by(2) {
+        assert_eq!(map.remove(&i), Some(i));
+    }
+    for (key, value) in map.iter_mut() {
+        *value = key * 10;
+    }
+    for i in 0..20_000 {
+        let expected = if i % 2 == 0 { None } else { Some(&(i * 10)) };
+        assert_eq!(map.get(&i), expected);
+    }
+}
+
+#[test]
+fn test_incremental_resize_finishes_before_next_resize() {
+    for growth_factor in [2.0, 1.5, 1.1] {
+        let mut map = TrashMap::builder()
+            .resize_mode(ResizeMode::Incremental { buckets_per_op: 1 })
+            .growth_factor(growth_factor)
+            .build()
+            .unwrap();
+        // a resize only starts once the previous one is drained by the step of this
+        // very operation, so no operation ever migrates a whole bucket array at once
+        let check = |map: &mut TrashMap<u32, u32>, op: &mut dyn FnMut(&mut TrashMap<u32, u32>)| {
+            let before = map
+                .migration
+                .as_ref()
+                .map(|m| (m.remaining().len(), m.step));
+            let buckets = map.buckets.len();
+            op(map);
+            if map.buckets.len() != buckets {
+                if let Some((remaining, step)) = before {
+                    assert!(
+                        remaining <= step,
+                        "{} buckets left, step {}",
+                        remaining,
+                        step
+                    );
+                }
+            }
+        };
+        let mut resizes = 0;
+        for i in 0..50_000 {
+            let buckets = map.buckets.len();
+            check(&mut map, &mut |map| {
+                map.insert(i, i);
+            });
+            resizes += (map.buckets.len() != buckets) as usize;
+        }
+        for i in 0..49_990 {
+            let buckets = map.buckets.len();
+            check(&mut map, &mut |map| {
+                map.remove(&i);
+            });
+            resizes += (map.buckets.len() != buckets) as usize;
+        }
+        assert!(resizes > 10, "{} resizes", resizes);
+        assert_eq!(map.len(), 10);
+        for i in 49_990..50_000 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+    }
+}
+
+#[test]
+fn test_incremental_resize_entry_and_finish() {
+    let mut map = TrashMap::new();
+    map.set_resize_mode(ResizeMode::Incremental { buckets_per_op: 1 });
+    for i in 0..1000 {
+        *map.entry(i % 700).or_insert(0) += 1;
+    }
+    assert_eq!(map.len(), 700);
+    while !map.is_resizing() {
+        map.insert(map.len(), 1);
+    }
+    assert_eq!(map.get_mut(&5), Some(&mut 2));
+    map.set_resize_mode(ResizeMode::Immediate);
+    assert!(!map.is_resizing());
+    for i in 0..map.len() {
+        let expected = if i < 300 { 2 } else { 1 };
+        assert_eq!(map.get(&i), Some(&expected));
+    }
+}
+
+#[test]
+fn test_growth_policy_validation() {
+    assert!(GrowthPolicy::new(0.9, 0.2, 1.5, 8).is_ok());
+    assert_eq!(
+        GrowthPolicy::new(0.0, 0.0, 2.0, 3),
+        Err(GrowthPolicyError::MaxLoadFactor)
+    );
+    assert_eq!(
+        GrowthPolicy::new(f32::NAN, 0.0, 2.0, 3),
+        Err(GrowthPolicyError::MaxLoadFactor)
+    );
+    assert_eq!(
+        GrowthPolicy::new(0.75, 0.0, 1.0, 3),
+        Err(GrowthPolicyError::GrowthFactor)
+    );
+    assert_eq!(
+        GrowthPolicy::new(0.75, 0.5, 2.0, 3),
+        Err(GrowthPolicyError::MinLoadFactor)
+    );
+    assert_eq!(
+        GrowthPolicy::new(0.75, -0.1, 2.0, 3),
+        Err(GrowthPolicyError::MinLoadFactor)
+    );
+    assert_eq!(
+        GrowthPolicy::new(0.75, 0.1, 2.0, 0),
+        Err(GrowthPolicyError::MinBuckets)
+    );
+    assert_eq!(
+        GrowthPolicy::new(0.75, 0.0, 1e30, 3),
+        Err(GrowthPolicyError::GrowthFactor)
+    );
+    assert_eq!(
+        GrowthPolicy::new(0.75, 0.0, f32::INFINITY, 3),
+        Err(GrowthPolicyError::GrowthFactor)
+    );
+    let mut map = TrashMap::builder()
+        .growth_factor(16.0)
+        .min_load_factor(0.0)
+        .build()
+        .unwrap();
+    for i in 0..100_000 {
+        map.insert(i, i);
+    }
+    assert_eq!(map.len(), 100_000);
+    assert_eq!(
+        GrowthPolicy::new(0.75, 0.1, 2.0, usize::MAX),
+        Err(GrowthPolicyError::MinBuckets)
+    );
+    assert_eq!(
+        TrashMap::builder()
+            .min_buckets(usize::MAX)
+            .build::<u32, u32>()
+            .err(),
+        Some(GrowthPolicyError::MinBuckets)
+    );
+    assert_eq!(
+        TrashMap::builder()
+            .capacity(usize::MAX)
+            .build::<u32, u32>()
+            .err(),
+        Some(GrowthPolicyError::CapacityOverflow)
+    );
+    assert_eq!(
+        TrashMap::builder()
+            .growth_factor(0.5)
+            .build::<u32, u32>()
+            .err(),
+        Some(GrowthPolicyError::GrowthFactor)
+    );
+}
+
+#[test]
+fn test_builder_growth_policy() {
+    for max_load_factor in [0.5, 0.9, 2.0] {
+        let mut map = TrashMap::builder()
+            .max_load_factor(max_load_factor)
+            .growth_factor(1.5)
+            .min_buckets(11)
+            .build()
+            .unwrap();
+        assert!(map.buckets.len() >= 11);
+        for i in 0..10_000 {
+            map.insert(i, i);
+            let load_factor = map.len() as f32 / map.buckets.len() as f32;
+            assert!(load_factor <= max_load_factor);
+        }
+        for i in 0..10_000 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+        assert_eq!(map.growth_policy().max_load_factor(), max_load_factor);
+    }
+}
+
+#[test]
+fn test_builder_options() {
+    let mut map = TrashMap::builder()
+        .hasher(BuildHasherDefault::<IdentityHasher>::default())
+        .indexing(BucketIndexing::PowerOfTwo)
+        .resize_mode(ResizeMode::Incremental { buckets_per_op: 4 })
+        .capacity(100)
+        .build()
+        .unwrap();
+    assert!(map.capacity() >= 100);
+    assert!(map.buckets.len().is_power_of_two());
+    for i in 0..1000u64 {
+        map.insert(i, ());
+    }
+    assert_eq!(map.indexing(), BucketIndexing::PowerOfTwo);
+    assert_eq!(
+        map.resize_mode(),
+        ResizeMode::Incremental { buckets_per_op: 4 }
+    );
+    assert_eq!(map.hasher().hash_one(5u64), 5);
+    assert_eq!(map.len(), 1000);
+}
+
+#[test]
+fn test_shrinks_after_mass_removal() {
+    let mut map = TrashMap::new();
+    for round in 0..5 {
+        for i in 0..20_000 {
+            map.insert(i, round);
+        }
+        let grown = map.buckets.len();
+        for i in 10..20_000 {
+            assert_eq!(map.remove(&i), Some(round));
+        }
+        assert!(
+            map.buckets.len() < grown / 100,
+            "{} buckets",
+            map.buckets.len()
+        );
+        assert!(map.compute_load_factor() >= map.growth_policy().min_load_factor());
+        for i in 0..10 {
+            assert_eq!(map.get(&i), Some(&round));
+        }
+        assert_eq!(map.iter().count(), 10);
+    }
+}
+
+#[test]
+fn test_auto_shrink_keeps_requested_capacity() {
+    let mut map = TrashMap::with_capacity(1000);
+    let capacity = map.capacity();
+    map.insert(0, 0);
+    map.remove(&0);
+    assert_eq!(map.capacity(), capacity);
+    for i in 0..20_000 {
+        map.insert(i, i);
+    }
+    for i in 0..20_000 {
+        map.remove(&i);
+    }
+    // grown past the requested capacity, then back down to it but no further
+    assert_eq!(map.capacity(), capacity);
+
+    // shrink_to lowers the floor, shrink_to_fit removes it
+    map.shrink_to(100);
+    let capacity = map.capacity();
+    assert!(capacity >= 100);
+    for i in 0..1000 {
+        map.insert(i, i);
+    }
+    for i in 0..1000 {
+        map.remove(&i);
+    }
+    assert_eq!(map.capacity(), capacity);
+    map.shrink_to_fit();
+    for i in 0..1000 {
+        map.insert(i, i);
+    }
+    for i in 0..1000 {
+        map.remove(&i);
+    }
+    assert!(map.capacity() < capacity);
+}
+
+#[test]
+fn test_auto_shrink_after_reserve() {
+    // reserving is a one-off hint, a bulk loaded map still shrinks once emptied
+    let mut map = TrashMap::new();
+    map.reserve(100_000);
+    let reserved = map.capacity();
+    for i in 0..100_000 {
+        map.insert(i, i);
+    }
+    assert_eq!(map.capacity(), reserved);
+    for i in 10..100_000 {
+        map.remove(&i);
+    }
+    assert!(map.capacity() < reserved / 100, "{}", map.capacity());
+
+    let mut map = TrashMap::new();
+    map.try_reserve(10_000).unwrap();
+    let reserved = map.capacity();
+    map.insert(0, 0);
+    map.insert(1, 1);
+    map.remove(&0);
+    assert!(map.capacity() < reserved);
+}
+
+#[test]
+fn test_shrink_to_huge_floor() {
+    let mut map = TrashMap::new();
+    for i in 0..100 {
+        map.insert(i, i);
+    }
+    let capacity = map.capacity();
+    map.shrink_to(usize::MAX);
+    assert_eq!(map.capacity(), capacity);
+    // the floor is the capacity the map actually has, not the one asked for
+    for i in 0..95 {
+        assert_eq!(map.remove(&i), Some(i));
     }
-    assert!(!map.remove(&0));
-    assert!(map.len() == 0);
+    assert_eq!(map.capacity(), capacity);
+    assert_eq!(map.len(), 5);
+}
+
+#[test]
+fn test_shrink_hysteresis() {
+    let mut map = TrashMap::new();
+    for i in 0..1000 {
+        map.insert(i, ());
+    }
+    // remove until the map shrinks for the first time
+    let grown = map.buckets.len();
+    let mut removed = 0;
+    while map.buckets.len() == grown {
+        assert!(map.remove(&removed).is_some(), "map never shrank");
+        removed += 1;
+    }
+    // going back and forth around the boundary must not resize again
+    let shrunk = map.buckets.len();
+    for _ in 0..100 {
+        removed -= 1;
+        map.insert(removed, ());
+        assert_eq!(map.buckets.len(), shrunk);
+        map.remove(&removed);
+        removed += 1;
+        assert_eq!(map.buckets.len(), shrunk);
+    }
+}
+
+#[test]
+fn test_shrink_incremental() {
+    let mut map = TrashMap::builder()
+        .resize_mode(ResizeMode::Incremental { buckets_per_op: 8 })
+        .build()
+        .unwrap();
+    for i in 0..10_000 {
+        map.insert(i, i);
+    }
+    for i in 0..9_990 {
+        assert_eq!(map.remove(&i), Some(i));
+        assert_eq!(map.get(&(i + 1)), Some(&(i + 1)));
+    }
+    for i in 9_990..10_000 {
+        assert_eq!(map.get(&i), Some(&i));
+    }
+    while map.is_resizing() {
+        map.insert(0, 0);
+    }
+    assert!(map.buckets.len() < 100);
+}
+
+thread_local! {
+    static HASH_CALLS: Cell<usize> = const { Cell::new(0) };
+    static EQ_CALLS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// A key that counts how often it is hashed and compared on the current thread.
+#[derive(Debug)]
+struct CountingKey(u64);
+
+impl Hash for CountingKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        HASH_CALLS.with(|calls| calls.set(calls.get() + 1));
+        self.0.hash(state);
+    }
+}
+
+impl PartialEq for CountingKey {
+    fn eq(&self, other: &Self) -> bool {
+        EQ_CALLS.with(|calls| calls.set(calls.get() + 1));
+        self.0 == other.0
+    }
+}
+
+impl Eq for CountingKey {}
+
+fn take_calls() -> (usize, usize) {
+    (
+        HASH_CALLS.with(|c| c.replace(0)),
+        EQ_CALLS.with(|c| c.replace(0)),
+    )
+}
+
+#[test]
+fn test_resize_never_rehashes() {
+    for resize_mode in [
+        ResizeMode::Immediate,
+        ResizeMode::Incremental { buckets_per_op: 4 },
+    ] {
+        let mut map = TrashMap::builder()
+            .resize_mode(resize_mode)
+            .build()
+            .unwrap();
+        take_calls();
+        for i in 0..10_000 {
+            map.insert(CountingKey(i), i);
+        }
+        // every insertion hashes its key once, no matter how often the map grew
+        assert_eq!(take_calls(), (10_000, 0));
+        map.reserve(100_000);
+        map.shrink_to_fit();
+        for i in 0..9_000 {
+            assert_eq!(map.remove(&CountingKey(i)), Some(i));
+        }
+        assert_eq!(take_calls(), (9_000, 9_000));
+        assert_eq!(map.iter().count(), 1_000);
+    }
+}
+
+#[test]
+fn test_lookups_compare_hashes_first() {
+    // long chains, so every lookup walks past several other elements
+    let mut map = TrashMap::builder().max_load_factor(8.0).build().unwrap();
+    for i in 0..1000 {
+        map.insert(CountingKey(i), i);
+    }
+    assert!(map.buckets.iter().any(|b| b.chain.len() > 4));
+    take_calls();
+    for i in 0..1000 {
+        assert_eq!(map.get(&CountingKey(i)), Some(&i));
+    }
+    // only the matching element is compared
+    assert_eq!(take_calls(), (1000, 1000));
+    for i in 1000..2000 {
+        assert_eq!(map.get(&CountingKey(i)), None);
+    }
+    assert_eq!(take_calls(), (1000, 0));
+}
+
+#[test]
+fn test_collect_and_compare() {
+    let map: TrashMap<u32, u32> = (0..100).map(|i| (i, i * 2)).collect();
+    assert_eq!(map.len(), 100);
+    let reversed: TrashMap<u32, u32> = (0..100).rev().map(|i| (i, i * 2)).collect();
+    assert_eq!(map, reversed);
+    // the same entries spread over a different number of buckets
+    let mut grown = TrashMap::with_capacity(10_000);
+    grown.extend(map.iter().map(|(&k, &v)| (k, v)));
+    assert_ne!(grown.buckets.len(), map.buckets.len());
+    assert_eq!(grown, map);
+
+    let mut other = map.clone();
+    assert_eq!(other, map);
+    other.insert(0, 1);
+    assert_ne!(other, map);
+    other.insert(0, 0);
+    assert_eq!(other, map);
+    other.insert(100, 200);
+    assert_ne!(other, map);
+    assert_ne!(map, other);
+}
+
+#[test]
+fn test_index_and_from_array() {
+    let map = TrashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+    assert_eq!(map["a"], 1);
+    assert_eq!(map[&"b".to_string()], 2);
+    assert_eq!(
+        map,
+        TrashMap::from([("b".to_string(), 2), ("a".to_string(), 1)])
+    );
+}
+
+#[test]
+#[should_panic(expected = "key not found")]
+fn test_index_missing_key() {
+    let map = TrashMap::from([(1, 1)]);
+    let _ = map[&2];
+}
+
+#[test]
+fn test_extend() {
+    let mut map = TrashMap::from([(1, 10), (2, 20)]);
+    map.extend([(2, 21), (3, 30)]);
+    let source = TrashMap::from([(4, 40), (1, 11)]);
+    map.extend(&source);
+    assert_eq!(map, TrashMap::from([(1, 11), (2, 21), (3, 30), (4, 40)]));
+}
+
+#[test]
+fn test_into_iterator() {
+    let mut map: TrashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+    for (_, value) in &mut map {
+        *value += 1;
+    }
+    let mut sum = 0;
+    for (key, value) in &map {
+        assert_eq!(*value, key + 1);
+        sum += value;
+    }
+    assert_eq!(sum, (1..=1000).sum());
+    let mut pairs: Vec<(u32, u32)> = map.into_iter().collect();
+    pairs.sort_unstable();
+    assert_eq!(pairs, (0..1000).map(|i| (i, i + 1)).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_iterators_during_incremental_resize() {
+    let mut map = TrashMap::builder()
+        .resize_mode(ResizeMode::Incremental { buckets_per_op: 1 })
+        .build()
+        .unwrap();
+    let mut i = 0;
+    while !map.is_resizing() || map.len() < 1000 {
+        map.insert(i, i);
+        i += 1;
+    }
+    assert!(map.is_resizing());
+    assert_eq!(map.iter().count(), map.len());
+    for (key, value) in map.iter_mut() {
+        *value = key * 2;
+    }
+    let clone = map.clone();
+    let mut pairs: Vec<_> = map.into_iter().collect();
+    pairs.sort_unstable();
+    assert_eq!(pairs, (0..i).map(|i| (i, i * 2)).collect::<Vec<_>>());
+    assert_eq!(clone.len(), pairs.len());
+    assert_eq!(clone, pairs.into_iter().collect());
+}
+
+#[test]
+fn test_debug_is_map_style() {
+    let mut map = TrashMap::new();
+    assert_eq!(format!("{:?}", map), "{}");
+    map.insert(1, "a");
+    assert_eq!(format!("{:?}", map), r#"{1: "a"}"#);
+    map.insert(2, "b");
+    let debug = format!("{:?}", map);
+    assert!(
+        debug == r#"{1: "a", 2: "b"}"# || debug == r#"{2: "b", 1: "a"}"#,
+        "{}",
+        debug
+    );
+    assert!(!format!("{:#?}", map).contains("bucket"));
+}
+
+#[test]
+fn test_debug_layout() {
+    let mut map: TrashMap<u64, &str, _> =
+        TrashMap::with_hasher(BuildHasherDefault::<IdentityHasher>::default());
+    assert_eq!(map.buckets.len(), 3);
+    map.insert(0, "a");
+    map.insert(3, "b");
+    assert_eq!(
+        map.debug_layout().to_string(),
+        "2 elements in 3 buckets (load factor 0.67, max 0.75)\n\
+         \x20 [0] 2: {0: \"a\", 3: \"b\"}\n\
+         \x20 [1..=2] empty\n"
+    );
+    map.remove(&0);
+    map.insert(5, "c");
+    assert_eq!(
+        format!("{:?}", map.debug_layout()),
+        "2 elements in 3 buckets (load factor 0.67, max 0.75)\n\
+         \x20 [0] 1: {3: \"b\"}\n\
+         \x20 [1] empty\n\
+         \x20 [2] 1: {5: \"c\"}\n"
+    );
+}
+
+#[test]
+fn test_debug_layout_during_resize() {
+    let mut map = TrashMap::builder()
+        .resize_mode(ResizeMode::Incremental { buckets_per_op: 1 })
+        .build()
+        .unwrap();
+    let mut i = 0;
+    while !map.is_resizing() {
+        map.insert(i, i);
+        i += 1;
+    }
+    let layout = map.debug_layout().to_string();
+    assert!(layout.contains("resizing, "), "{}", layout);
+    // every element shows up exactly once, in the new or the old buckets
+    for key in 0..i {
+        let needle = format!("{}: {}", key, key);
+        assert_eq!(layout.matches(&needle).count(), 1, "{}", layout);
+    }
+}
+
+#[test]
+fn test_clear_keeps_capacity() {
+    let mut map: TrashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+    let buckets = map.buckets.len();
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.iter().count(), 0);
+    assert_eq!(map.buckets.len(), buckets);
+    assert_eq!(map.get(&1), None);
+    map.insert(1, 2);
+    assert_eq!(map[&1], 2);
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn test_drain() {
+    let mut map: TrashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+    let buckets = map.buckets.len();
+    let mut drained: Vec<_> = map.drain().collect();
+    drained.sort_unstable();
+    assert_eq!(drained, (0..1000).map(|i| (i, i)).collect::<Vec<_>>());
+    assert!(map.is_empty());
+    assert_eq!(map.buckets.len(), buckets);
+
+    map.extend((0..1000).map(|i| (i, i)));
+    // a partially consumed drain still empties the map
+    assert_eq!(map.drain().take(10).count(), 10);
+    assert!(map.is_empty());
+    assert_eq!(map.iter().count(), 0);
+
+    map.extend((0..1000).map(|i| (i, i)));
+    let mut drain = map.drain();
+    drain.next();
+    std::mem::forget(drain);
+    // a leaked drain loses entries, but never miscounts them
+    assert_eq!(map.iter().count(), map.len());
+}
+
+#[test]
+fn test_drain_during_incremental_resize() {
+    let mut map = TrashMap::builder()
+        .resize_mode(ResizeMode::Incremental { buckets_per_op: 1 })
+        .build()
+        .unwrap();
+    let mut i = 0;
+    while !map.is_resizing() || map.len() < 100 {
+        map.insert(i, i);
+        i += 1;
+    }
+    assert_eq!(map.drain().count(), i);
+    assert!(map.is_empty());
+    assert!(!map.is_resizing());
+    map.insert(0, 0);
+    assert_eq!(map.iter().count(), 1);
+}
+
+#[test]
+fn test_retain() {
+    let mut map: TrashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+    map.retain(|key, value| {
+        *value *= 2;
+        key % 3 == 0
+    });
+    assert_eq!(map.len(), 334);
+    assert_eq!(map.iter().count(), 334);
+    for i in 0..1000 {
+        let expected = if i % 3 == 0 { Some(&(i * 2)) } else { None };
+        assert_eq!(map.get(&i), expected);
+    }
+    // removing almost everything shrinks the map
+    let buckets = map.buckets.len();
+    map.retain(|&key, _| key < 10);
+    assert_eq!(map.len(), 4);
+    assert!(map.buckets.len() < buckets);
+    assert_eq!(map.iter().count(), 4);
+}
+
+#[test]
+fn test_retain_long_chains() {
+    let mut map: TrashMap<u32, u32, BuildHasherDefault<ConstantHasher>> = TrashMap::default();
+    for i in 0..100 {
+        map.insert(i, i);
+    }
+    map.retain(|&key, _| key % 2 == 0);
+    assert_eq!(map.len(), 50);
+    for i in 0..100 {
+        assert_eq!(map.contains_key(&i), i % 2 == 0);
+    }
+}
+
+#[test]
+fn test_extract_if() {
+    let mut map: TrashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+    // nothing happens until the iterator advances
+    let _ = map.extract_if(|_, _| true);
+    assert_eq!(map.len(), 1000);
+
+    let mut extracted: Vec<_> = map.extract_if(|key, _| key % 2 == 1).collect();
+    extracted.sort_unstable();
+    assert_eq!(
+        extracted,
+        (0..500).map(|i| (2 * i + 1, 2 * i + 1)).collect::<Vec<_>>()
+    );
+    assert_eq!(map.len(), 500);
+    assert_eq!(map.iter().count(), 500);
+    assert!(map.iter().all(|(key, _)| key % 2 == 0));
+
+    // stopping early keeps the rest
+    let taken: Vec<_> = map.extract_if(|_, _| true).take(100).collect();
+    assert_eq!(taken.len(), 100);
+    assert_eq!(map.len(), 400);
+    for (key, _) in taken {
+        assert!(!map.contains_key(&key));
+    }
+    assert_eq!(map.iter().count(), 400);
+}
+
+#[test]
+fn test_exact_size_iterators() {
+    let mut map: TrashMap<u32, u32> = (0..1000).map(|i| (i, i + 1)).collect();
+    let mut iter = map.iter();
+    assert_eq!(iter.len(), 1000);
+    assert_eq!(iter.size_hint(), (1000, Some(1000)));
+    iter.next();
+    iter.next_back();
+    assert_eq!(iter.len(), 998);
+    let copy = iter.clone();
+    assert_eq!(copy.count(), 998);
+    assert_eq!(iter.len(), 998);
+
+    assert_eq!(map.keys().len(), 1000);
+    assert_eq!(map.values().len(), 1000);
+    assert_eq!(map.values_mut().len(), 1000);
+    assert_eq!(map.iter_mut().len(), 1000);
+    assert_eq!(map.clone().into_iter().len(), 1000);
+    assert_eq!(map.clone().into_keys().len(), 1000);
+    assert_eq!(map.clone().into_values().len(), 1000);
+
+    let keys: Vec<_> = map.keys().copied().collect();
+    assert_eq!(keys.capacity(), 1000);
+    let values: Vec<_> = map.values().copied().collect();
+    assert_eq!(values.capacity(), 1000);
+    // keys and values are visited in the same order
+    for (key, value) in keys.iter().zip(&values) {
+        assert_eq!(key + 1, *value);
+    }
+
+    for value in map.values_mut() {
+        *value = 0;
+    }
+    assert!(map.values().all(|&value| value == 0));
+    let mut keys: Vec<_> = map.clone().into_keys().collect();
+    keys.sort_unstable();
+    assert_eq!(keys, (0..1000).collect::<Vec<_>>());
+    assert_eq!(map.into_values().sum::<u32>(), 0);
+}
+
+#[test]
+fn test_double_ended_iterators() {
+    let map: TrashMap<u32, u32> = (0..100).map(|i| (i, i)).collect();
+    let forward: Vec<_> = map.iter().collect();
+    let mut backward: Vec<_> = map.iter().rev().collect();
+    backward.reverse();
+    assert_eq!(forward, backward);
+
+    // both ends meet in the middle without yielding an element twice
+    let mut iter = map.keys();
+    let mut seen = Vec::new();
+    while let Some(&key) = iter.next() {
+        seen.push(key);
+        if let Some(&key) = iter.next_back() {
+            seen.push(key);
+        }
+        assert_eq!(iter.len(), 100 - seen.len());
+    }
+    assert_eq!(iter.next_back(), None);
+    assert_eq!(iter.next(), None);
+    seen.sort_unstable();
+    assert_eq!(seen, (0..100).collect::<Vec<_>>());
+
+    let mut values = map.into_values();
+    let last = values.next_back().unwrap();
+    assert_eq!(values.len(), 99);
+    assert!(!values.any(|value| value == last));
+}
+
+#[test]
+fn test_iterator_len_during_incremental_resize() {
+    let mut map = TrashMap::builder()
+        .resize_mode(ResizeMode::Incremental { buckets_per_op: 1 })
+        .build()
+        .unwrap();
+    let mut i = 0;
+    while !map.is_resizing() || map.len() < 100 {
+        map.insert(i, i);
+        i += 1;
+    }
+    let mut iter = map.iter();
+    for remaining in (0..map.len()).rev() {
+        assert!(iter.next().is_some());
+        assert_eq!(iter.len(), remaining);
+    }
+    assert_eq!(iter.next(), None);
+    assert_eq!(map.keys().rev().count(), map.len());
+    assert_eq!(map.into_iter().rev().count(), i);
+}
+
+#[test]
+fn test_set_basics() {
+    let mut set = TrashSet::new();
+    assert!(set.insert("a".to_string()));
+    assert!(!set.insert("a".to_string()));
+    assert!(set.insert("b".to_string()));
+    assert_eq!(set.len(), 2);
+    assert!(set.contains("a"));
+    assert!(!set.contains("c"));
+    assert_eq!(set.get("b").map(String::as_str), Some("b"));
+    assert!(set.remove("a"));
+    assert!(!set.remove("a"));
+    assert_eq!(set.take("b"), Some("b".to_string()));
+    assert_eq!(set.take("b"), None);
+    assert!(set.is_empty());
+
+    let set: TrashSet<u32> = (0..1000).collect();
+    assert_eq!(set.len(), 1000);
+    assert_eq!(set.iter().len(), 1000);
+    let mut values: Vec<_> = set.into_iter().collect();
+    values.sort_unstable();
+    assert_eq!(values, (0..1000).collect::<Vec<_>>());
+}
+
+/// Equal to every other `Tagged` with the same id, whatever the tag.
+#[derive(Debug)]
+struct Tagged(u32, &'static str);
+
+impl PartialEq for Tagged {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Tagged {}
+
+impl Hash for Tagged {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+#[test]
+fn test_set_insert_keeps_and_replace_swaps() {
+    let mut set = TrashSet::new();
+    assert!(set.insert(Tagged(1, "first")));
+    assert!(!set.insert(Tagged(1, "second")));
+    assert_eq!(set.get(&Tagged(1, "")).unwrap().1, "first");
+    assert_eq!(set.replace(Tagged(1, "third")).unwrap().1, "first");
+    assert_eq!(set.get(&Tagged(1, "")).unwrap().1, "third");
+    assert!(set.replace(Tagged(2, "other")).is_none());
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_set_operations() {
+    let a: TrashSet<u32> = (0..10).collect();
+    let b: TrashSet<u32> = (5..20).collect();
+    let sorted = |iter: &mut dyn Iterator<Item = &u32>| {
+        let mut values: Vec<u32> = iter.copied().collect();
+        values.sort_unstable();
+        values
+    };
+    assert_eq!(sorted(&mut a.union(&b)), (0..20).collect::<Vec<_>>());
+    assert_eq!(sorted(&mut b.union(&a)), (0..20).collect::<Vec<_>>());
+    assert_eq!(sorted(&mut a.intersection(&b)), (5..10).collect::<Vec<_>>());
+    assert_eq!(sorted(&mut b.intersection(&a)), (5..10).collect::<Vec<_>>());
+    assert_eq!(sorted(&mut a.difference(&b)), (0..5).collect::<Vec<_>>());
+    assert_eq!(sorted(&mut b.difference(&a)), (10..20).collect::<Vec<_>>());
+    assert_eq!(
+        sorted(&mut a.symmetric_difference(&b)),
+        (0..5).chain(10..20).collect::<Vec<_>>()
+    );
+
+    assert_eq!(&a | &b, (0..20).collect());
+    assert_eq!(&a & &b, (5..10).collect());
+    assert_eq!(&a - &b, (0..5).collect());
+    assert_eq!(&a ^ &b, (0..5).chain(10..20).collect());
+
+    let small: TrashSet<u32> = (2..4).collect();
+    assert!(small.is_subset(&a));
+    assert!(!small.is_subset(&b));
+    assert!(a.is_superset(&small));
+    assert!(!small.is_superset(&a));
+    assert!(small.is_disjoint(&b));
+    assert!(!a.is_disjoint(&b));
+    assert!(TrashSet::<u32>::new().is_subset(&small));
+}
+
+#[test]
+fn test_set_operations_are_lazy() {
+    let a: TrashSet<u32> = (0..1000).collect();
+    let b: TrashSet<u32> = (500..1500).collect();
+    let mut union = a.union(&b);
+    assert!(union.next().is_some());
+    assert_eq!(union.count(), 1499);
+    let mut intersection = a.intersection(&b);
+    assert!(intersection.next().is_some());
+    assert_eq!(intersection.count(), 499);
+}
+
+#[test]
+fn test_set_traits() {
+    let mut set = TrashSet::from([3, 1, 2]);
+    assert_eq!(set, TrashSet::from([1, 2, 3]));
+    assert_ne!(set, TrashSet::from([1, 2]));
+    set.extend([4, 5]);
+    set.extend(&[6]);
+    assert_eq!(set.len(), 6);
+    let clone = set.clone();
+    set.retain(|value| value % 2 == 0);
+    assert_eq!(set, TrashSet::from([2, 4, 6]));
+    assert_eq!(clone.len(), 6);
+    assert_eq!(format!("{:?}", TrashSet::from([7])), "{7}");
+    let mut drained: Vec<_> = set.drain().collect();
+    drained.sort_unstable();
+    assert_eq!(drained, [2, 4, 6]);
+    assert!(set.is_empty());
+    let mut sum = 0;
+    for value in &clone {
+        sum += value;
+    }
+    assert_eq!(sum, 21);
+}
+
+/// Checks that every key is found at its position in the entry order.
+fn assert_positions<K: Hash + Eq + std::fmt::Debug, V>(map: &OrderedTrashMap<K, V>) {
+    for (index, (key, _)) in map.iter().enumerate() {
+        assert_eq!(map.get_index_of(key), Some(index), "{:?}", key);
+    }
+}
+
+#[test]
+fn test_ordered_insertion_order() {
+    let mut map = OrderedTrashMap::new();
+    // insert in an order that bucket order would scramble, across several resizes
+    for i in (0..1000).rev() {
+        assert_eq!(map.insert(i * 7 % 1000, i), None);
+    }
+    let keys: Vec<_> = map.keys().copied().collect();
+    assert_eq!(
+        keys,
+        (0..1000).rev().map(|i| i * 7 % 1000).collect::<Vec<_>>()
+    );
+    let mut values = map.values();
+    assert_eq!(values.next(), Some(&999));
+    assert_eq!(values.next_back(), Some(&0));
+    assert_eq!(values.len(), 998);
+    assert_eq!(values.clone().copied().sum::<i32>(), (1..999).sum());
+    let mut rest = map.keys().skip(999);
+    assert_eq!(rest.next(), keys.last());
+    assert_eq!(rest.next(), None);
+    assert_eq!(rest.next(), None);
+    // updating a value keeps the key's position
+    assert_eq!(map.insert(keys[10], 0), Some(989));
+    assert_eq!(map.get_index(10), Some((&keys[10], &0)));
+    assert_eq!(map.insert_full(5000, 1), (1000, None));
+    assert_eq!(map.first(), Some((&keys[0], &999)));
+    assert_eq!(map.last(), Some((&5000, &1)));
+    assert_eq!(map.get_index(1001), None);
+    assert_positions(&map);
+
+    let a = OrderedTrashMap::from([("x", 1), ("y", 2), ("z", 3)]);
+    assert_eq!(format!("{:?}", a), r#"{"x": 1, "y": 2, "z": 3}"#);
+    assert_eq!(a, OrderedTrashMap::from([("z", 3), ("y", 2), ("x", 1)]));
+    assert_eq!(a["y"], 2);
+    let pairs: Vec<_> = a.into_iter().rev().collect();
+    assert_eq!(pairs, [("z", 3), ("y", 2), ("x", 1)]);
+}
+
+#[test]
+fn test_ordered_removal() {
+    let mut map: OrderedTrashMap<u32, u32> = (0..10).map(|i| (i, i)).collect();
+    assert_eq!(map.swap_remove(&2), Some(2));
+    assert_eq!(map.swap_remove(&2), None);
+    assert_eq!(
+        map.keys().copied().collect::<Vec<_>>(),
+        [0, 1, 9, 3, 4, 5, 6, 7, 8]
+    );
+    assert_positions(&map);
+
+    assert_eq!(map.shift_remove(&3), Some(3));
+    assert_eq!(map.shift_remove(&3), None);
+    assert_eq!(
+        map.keys().copied().collect::<Vec<_>>(),
+        [0, 1, 9, 4, 5, 6, 7, 8]
+    );
+    assert_positions(&map);
+
+    assert_eq!(map.shift_remove_index(0), Some((0, 0)));
+    assert_eq!(map.swap_remove_index(100), None);
+    assert_eq!(map.pop(), Some((8, 8)));
+    assert_eq!(map.swap_remove_index(map.len() - 1), Some((7, 7)));
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), [1, 9, 4, 5, 6]);
+    assert_positions(&map);
+
+    map.retain(|key, _| key % 2 == 1);
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), [1, 9, 5]);
+    assert_positions(&map);
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.get(&1), None);
+}
+
+#[test]
+fn test_ordered_move_index() {
+    let mut map: OrderedTrashMap<u32, ()> = (0..6).map(|i| (i, ())).collect();
+    map.move_index(1, 4);
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), [0, 2, 3, 4, 1, 5]);
+    assert_positions(&map);
+    map.move_index(5, 0);
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), [5, 0, 2, 3, 4, 1]);
+    assert_positions(&map);
+    map.move_index(3, 3);
+    assert_eq!(map.keys().copied().collect::<Vec<_>>(), [5, 0, 2, 3, 4, 1]);
+    assert_positions(&map);
+}
+
+#[test]
+#[should_panic(expected = "index out of bounds")]
+fn test_ordered_move_index_out_of_bounds() {
+    let mut map = OrderedTrashMap::from([(1, 1)]);
+    map.move_index(0, 1);
+}
+
+#[test]
+fn test_ordered_sort() {
+    let mut map: OrderedTrashMap<u32, u32> = (0..100).map(|i| (i * 37 % 100, i)).collect();
+    map.sort_keys();
+    assert_eq!(
+        map.keys().copied().collect::<Vec<_>>(),
+        (0..100).collect::<Vec<_>>()
+    );
+    assert_positions(&map);
+    map.sort_by(|_, a, _, b| b.cmp(a));
+    let values: Vec<_> = map.values().copied().collect();
+    assert_eq!(values, (0..100).rev().collect::<Vec<_>>());
+    assert_positions(&map);
+    for (_, value) in &mut map {
+        *value = 0;
+    }
+    assert!(map.values().all(|&value| value == 0));
+}
+
+#[test]
+fn test_ordered_colliding_hashes() {
+    // every key lands in the same bucket and even shares its hash
+    let mut map: OrderedTrashMap<u32, u32, BuildHasherDefault<ConstantHasher>> =
+        OrderedTrashMap::default();
+    for i in 0..50 {
+        map.insert(i, i);
+    }
+    map.move_index(0, 49);
+    map.shift_remove(&10);
+    map.swap_remove(&20);
+    for i in 0..50 {
+        let expected = if i == 10 || i == 20 { None } else { Some(&i) };
+        assert_eq!(map.get(&i), expected);
+    }
+    for (index, (key, _)) in map.iter().enumerate() {
+        assert_eq!(map.get_index_of(key), Some(index));
+    }
+}
+
+#[test]
+fn test_lru_eviction_order() {
+    let mut cache = LruTrashMap::new(3);
+    assert_eq!(cache.put("a", 1), None);
+    assert_eq!(cache.put("b", 2), None);
+    assert_eq!(cache.put("c", 3), None);
+    // touching "a" makes "b" the least recently used
+    assert_eq!(cache.get("a"), Some(&1));
+    assert_eq!(cache.put("d", 4), Some(Put::Evicted("b", 2)));
+    assert_eq!(cache.len(), 3);
+    assert!(!cache.contains_key("b"));
+    // peeking does not promote, so "c" goes next
+    assert_eq!(cache.peek("c"), Some(&3));
+    assert_eq!(cache.peek_lru(), Some((&"c", &3)));
+    assert_eq!(cache.put("e", 5), Some(Put::Evicted("c", 3)));
+    let order: Vec<_> = cache.iter().map(|(key, _)| *key).collect();
+    assert_eq!(order, ["e", "d", "a"]);
+    let reversed: Vec<_> = cache.iter().rev().map(|(key, _)| *key).collect();
+    assert_eq!(reversed, ["a", "d", "e"]);
+    assert_eq!(format!("{:?}", cache), r#"{"e": 5, "d": 4, "a": 1}"#);
+}
+
+#[test]
+fn test_lru_put_existing_key() {
+    let mut cache = LruTrashMap::new(2);
+    cache.put(1, "one");
+    cache.put(2, "two");
+    // replacing a value promotes the key and evicts nothing
+    assert_eq!(cache.put(1, "uno"), Some(Put::Replaced("one")));
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.put(3, "three"), Some(Put::Evicted(2, "two")));
+    *cache.get_mut(&1).unwrap() = "eins";
+    assert_eq!(cache.peek(&1), Some(&"eins"));
+}
+
+#[test]
+fn test_lru_pop_and_resize() {
+    let mut cache = LruTrashMap::new(5);
+    for i in 0..5 {
+        cache.put(i, i * 10);
+    }
+    assert_eq!(cache.pop_lru(), Some((0, 0)));
+    assert_eq!(cache.pop(&3), Some(30));
+    assert_eq!(cache.pop(&3), None);
+    assert_eq!(cache.len(), 3);
+
+    cache.resize(1);
+    assert_eq!(cache.capacity(), 1);
+    assert_eq!(cache.iter().collect::<Vec<_>>(), [(&4, &40)]);
+    assert_eq!(cache.put(5, 50), Some(Put::Evicted(4, 40)));
+    cache.resize(3);
+    assert_eq!(cache.put(6, 60), None);
+    assert_eq!(cache.put(7, 70), None);
+    assert_eq!(cache.put(8, 80), Some(Put::Evicted(5, 50)));
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.pop_lru(), None);
+    assert_eq!(cache.peek_lru(), None);
+    assert_eq!(cache.put(1, 1), None);
+
+    let mut empty = LruTrashMap::new(0);
+    assert_eq!(empty.put(1, 1), Some(Put::Evicted(1, 1)));
+    assert!(empty.is_empty());
+
+    // the capacity is a limit, not an allocation
+    let mut huge = LruTrashMap::new(usize::MAX);
+    for i in 0..1000 {
+        assert_eq!(huge.put(i, i), None);
+    }
+    assert_eq!(huge.len(), 1000);
+    assert_eq!(huge.capacity(), usize::MAX);
+    assert_eq!(huge.peek_lru(), Some((&0, &0)));
+    let mut large = LruTrashMap::new(1 << 40);
+    large.put(1, 1);
+    assert_eq!(large.get(&1), Some(&1));
+}
+
+#[test]
+fn test_lru_matches_model() {
+    // a tiny xorshift keeps the sequence of operations reproducible
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut random = move |bound: u64| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state % bound
+    };
+    let mut cache = LruTrashMap::new(16);
+    // most recently used first
+    let mut model: Vec<(u64, u64)> = Vec::new();
+    for step in 0..20_000 {
+        let key = random(40);
+        match random(4) {
+            0 | 1 => {
+                let expected = match model.iter().position(|&(k, _)| k == key) {
+                    Some(index) => Some(Put::Replaced(model.remove(index).1)),
+                    None if model.len() == 16 => model.pop().map(|(k, v)| Put::Evicted(k, v)),
+                    None => None,
+                };
+                model.insert(0, (key, step));
+                assert_eq!(cache.put(key, step), expected);
+            }
+            2 => {
+                let expected = model.iter().position(|&(k, _)| k == key).map(|index| {
+                    let entry = model.remove(index);
+                    model.insert(0, entry);
+                    entry.1
+                });
+                assert_eq!(cache.get(&key).copied(), expected);
+            }
+            _ => {
+                let expected = model
+                    .iter()
+                    .position(|&(k, _)| k == key)
+                    .map(|index| model.remove(index).1);
+                assert_eq!(cache.pop(&key), expected);
+            }
+        }
+        let entries: Vec<_> = cache.iter().map(|(&k, &v)| (k, v)).collect();
+        assert_eq!(entries, model);
+    }
+}
+
+/// A clock that only moves when told to. Clones share the same time.
+#[derive(Clone)]
+struct MockClock(Rc<Cell<Instant>>);
+
+impl MockClock {
+    fn new() -> Self {
+        MockClock(Rc::new(Cell::new(Instant::now())))
+    }
+
+    fn advance(&self, by: Duration) {
+        self.0.set(self.0.get() + by);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+}
+
+#[test]
+fn test_expiring_ttl() {
+    let clock = MockClock::new();
+    let mut map = ExpiringTrashMap::with_clock(clock.clone());
+    map.insert_with_ttl("session", 1, Duration::from_secs(10));
+    map.insert("forever", 2);
+    assert_eq!(map.get("session"), Some(&1));
+    assert_eq!(map.ttl("session"), Some(Duration::from_secs(10)));
+    assert_eq!(map.ttl("forever"), None);
+
+    clock.advance(Duration::from_secs(9));
+    assert_eq!(map.get("session"), Some(&1));
+    assert_eq!(map.ttl("session"), Some(Duration::from_secs(1)));
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(map.get("session"), None);
+    assert!(!map.contains_key("session"));
+    assert_eq!(map.ttl("session"), None);
+    assert_eq!(map.get("forever"), Some(&2));
+    assert_eq!(format!("{:?}", map), r#"{"forever": 2}"#);
+
+    // expired entries keep their memory until they are reclaimed
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.purge_expired(), 1);
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.purge_expired(), 0);
+}
+
+#[test]
+fn test_expiring_huge_ttl_never_expires() {
+    let clock = MockClock::new();
+    let mut map = ExpiringTrashMap::with_clock(clock.clone());
+    assert_eq!(map.insert_with_ttl("max", 1, Duration::MAX), None);
+    assert_eq!(map.insert_with_ttl("max", 2, Duration::MAX), Some(1));
+    assert_eq!(map.ttl("max"), None);
+    clock.advance(Duration::from_secs(365 * 24 * 60 * 60));
+    assert_eq!(map.get("max"), Some(&2));
+    assert_eq!(map.purge_expired(), 0);
+}
+
+#[test]
+fn test_expiring_replace_and_remove() {
+    let clock = MockClock::new();
+    let mut map = ExpiringTrashMap::with_clock(clock.clone());
+    assert_eq!(map.insert_with_ttl(1, "a", Duration::from_secs(1)), None);
+    assert_eq!(
+        map.insert_with_ttl(1, "b", Duration::from_secs(1)),
+        Some("a")
+    );
+    clock.advance(Duration::from_secs(1));
+    // an expired value is not handed back
+    assert_eq!(map.insert_with_ttl(1, "c", Duration::from_secs(1)), None);
+    assert_eq!(map.remove(&1), Some("c"));
+
+    map.insert_with_ttl(2, "d", Duration::from_secs(1));
+    clock.advance(Duration::from_secs(2));
+    assert_eq!(map.remove(&2), None);
+    assert!(map.is_empty());
+
+    map.insert_with_ttl(3, "e", Duration::from_secs(1));
+    *map.get_mut(&3).unwrap() = "f";
+    assert_eq!(map.get(&3), Some(&"f"));
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(map.get_mut(&3), None);
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_expiring_iter_skips_expired() {
+    let clock = MockClock::new();
+    let mut map = ExpiringTrashMap::with_clock(clock.clone());
+    for i in 0..100u64 {
+        map.insert_with_ttl(i, i, Duration::from_secs(i));
+    }
+    clock.advance(Duration::from_secs(50));
+    let mut live: Vec<_> = map.iter().map(|(&key, _)| key).collect();
+    live.sort_unstable();
+    assert_eq!(live, (51..100).collect::<Vec<_>>());
+    assert_eq!(map.len(), 100);
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_expiring_lazy_sampling() {
+    let clock = MockClock::new();
+    let mut map = ExpiringTrashMap::with_clock(clock.clone());
+    for i in 0..1000 {
+        map.insert_with_ttl(i, (), Duration::from_secs(1));
+    }
+    clock.advance(Duration::from_secs(1));
+
+    // without sampling, nothing is reclaimed behind the scenes
+    for i in 1000..1010 {
+        map.insert(i, ());
+    }
+    assert_eq!(map.len(), 1010);
+
+    map.set_sample_size(16);
+    let mut key = 1010;
+    while map.len() > 10 + (key - 1010) {
+        map.insert(key, ());
+        key += 1;
+        assert!(key < 10_000, "sampling never reclaimed everything");
+    }
+    assert_eq!(map.iter().count(), map.len());
+    assert_eq!(map.purge_expired(), 0);
+}
+
+#[test]
+fn test_multimap_insert_and_get() {
+    let mut map = TrashMultiMap::new();
+    map.insert("fruit", "apple");
+    map.insert("fruit", "pear");
+    map.insert("veg", "leek");
+    map.insert("fruit", "apple");
+    assert_eq!(map.len(), 4);
+    assert_eq!(map.key_count(), 2);
+    assert_eq!(map.get_all("fruit"), ["apple", "pear", "apple"]);
+    assert_eq!(map.get("fruit"), Some(&"apple"));
+    assert_eq!(map.get_all("nut"), [] as [&str; 0]);
+    assert_eq!(map.get("nut"), None);
+    assert!(map.contains_key("veg"));
+    map.get_all_mut("veg")[0] = "onion";
+    assert_eq!(format!("{:?}", map.get_all("veg")), r#"["onion"]"#);
+    assert_eq!(
+        format!(
+            "{:?}",
+            TrashMultiMap::<_, _>::from_iter([(1, 'a'), (1, 'b')])
+        ),
+        "{1: ['a', 'b']}"
+    );
+}
+
+#[test]
+fn test_multimap_remove() {
+    let mut map: TrashMultiMap<u32, u32> = (0..30).map(|i| (i % 3, i)).collect();
+    assert_eq!(map.len(), 30);
+    assert_eq!(map.key_count(), 3);
+    assert_eq!(map.remove_one(&0), Some(27));
+    assert_eq!(map.len(), 29);
+    assert_eq!(
+        map.remove_all(&1),
+        (0..10).map(|i| i * 3 + 1).collect::<Vec<_>>()
+    );
+    assert_eq!(map.remove_all(&1), Vec::<u32>::new());
+    assert_eq!(map.len(), 19);
+    assert_eq!(map.key_count(), 2);
+    // removing the last value removes the key
+    while map.remove_one(&2).is_some() {}
+    assert_eq!(map.key_count(), 1);
+    assert!(!map.contains_key(&2));
+    assert_eq!(map.remove_one(&2), None);
+    assert_eq!(map.len(), 9);
+
+    map.retain(|_, &value| value % 2 == 0);
+    assert_eq!(map.get_all(&0), [0, 6, 12, 18, 24]);
+    assert_eq!(map.len(), 5);
+    map.retain(|_, _| false);
+    assert_eq!(map.key_count(), 0);
+    assert!(map.is_empty());
+    map.insert(1, 1);
+    map.clear();
+    assert!(map.is_empty());
+}
+
+#[test]
+fn test_multimap_iteration() {
+    let map: TrashMultiMap<u32, u32> = (0..100).map(|i| (i % 7, i)).collect();
+    let mut iter = map.iter();
+    assert_eq!(iter.len(), 100);
+    iter.next();
+    assert_eq!(iter.len(), 99);
+    let mut pairs: Vec<_> = map.iter().map(|(&k, &v)| (k, v)).collect();
+    pairs.sort_unstable();
+    let mut expected: Vec<_> = (0..100).map(|i| (i % 7, i)).collect();
+    expected.sort_unstable();
+    assert_eq!(pairs, expected);
+    // values of one key come out together and in insertion order
+    for (key, values) in map.iter_grouped() {
+        assert_eq!(
+            values,
+            (0..100).filter(|i| i % 7 == *key).collect::<Vec<_>>()
+        );
+    }
+    assert_eq!(map.iter_grouped().len(), 7);
+    assert_eq!(map.keys().len(), 7);
+    let mut keys: Vec<_> = map.keys().clone().copied().collect();
+    keys.sort_unstable();
+    assert_eq!(keys, (0..7).collect::<Vec<_>>());
+    let mut count = 0;
+    for (key, value) in &map {
+        assert_eq!(value % 7, *key);
+        count += 1;
+    }
+    assert_eq!(count, 100);
+
+    let clone = map.clone();
+    assert_eq!(clone, map);
+    let mut other = map.clone();
+    other.insert(0, 0);
+    assert_ne!(other, map);
 }